@@ -0,0 +1,67 @@
+//! Latency and throughput of the route queries against a seeded database.
+//!
+//! Only runs when `BENCH_DATABASE_URL` points at a database loaded with the
+//! full dataset, e.g.
+//!
+//! ```sh
+//! BENCH_DATABASE_URL=mysql://root@localhost/pokemon cargo bench -p pokemon-api
+//! ```
+
+use std::env;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use pokemon_api::queries::{self, PokemonTable};
+use sqlx::mysql::MySqlPoolOptions;
+use tokio::runtime::Runtime;
+
+fn query_path(c: &mut Criterion) {
+    let Ok(database_url) = env::var("BENCH_DATABASE_URL") else {
+        eprintln!("BENCH_DATABASE_URL is not set, skipping the query benchmarks");
+        return;
+    };
+    let runtime = Runtime::new().unwrap();
+    let pool = runtime
+        .block_on(
+            MySqlPoolOptions::new()
+                .max_connections(5)
+                .connect(&database_url),
+        )
+        .expect("could not connect to BENCH_DATABASE_URL");
+    let pool = &pool;
+
+    let mut group = c.benchmark_group("queries");
+
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("pokemon_by_slug", |b| {
+        b.to_async(&runtime).iter(|| async move {
+            queries::pokemon_by_slug(pool, PokemonTable::Pokemon, "squirtle")
+                .await
+                .unwrap()
+                .expect("squirtle is seeded")
+        })
+    });
+
+    group.throughput(Throughput::Elements(151));
+    group.bench_function("pokedex_range", |b| {
+        b.to_async(&runtime).iter(|| async move {
+            queries::pokedex_range(pool, PokemonTable::Pokemon, 1, 151, false)
+                .await
+                .unwrap()
+        })
+    });
+
+    let slugs = ["ho-oh", "squirtle", "bulbasaur", "pikachu", "mewtwo"];
+    group.throughput(Throughput::Elements(slugs.len() as u64));
+    group.bench_function("pokemon_by_slugs", |b| {
+        b.to_async(&runtime).iter(|| async move {
+            queries::pokemon_by_slugs(pool, PokemonTable::Pokemon, &slugs)
+                .await
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, query_path);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+use std::{borrow::Cow, env};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+
+use crate::error::ApiError;
+
+/// The largest request body we'll read, from `MAX_REQUEST_BODY_BYTES`.
+/// Defaults to 64KiB, far more than any correction needs.
+static MAX_BODY_BYTES: Lazy<usize> = Lazy::new(|| {
+    env::var("MAX_REQUEST_BODY_BYTES")
+        .ok()
+        .and_then(|bytes| bytes.parse().ok())
+        .unwrap_or(64 * 1024)
+});
+
+/// The request body as text, decoding it first if API Gateway sent it as
+/// base64. Oversized bodies are turned away with a 413 before any decoding
+/// or parsing.
+pub fn decode(body: Option<&str>, is_base64_encoded: bool) -> Result<Option<Cow<str>>, ApiError> {
+    decode_with_limit(body, is_base64_encoded, *MAX_BODY_BYTES)
+}
+
+fn decode_with_limit(
+    body: Option<&str>,
+    is_base64_encoded: bool,
+    max_bytes: usize,
+) -> Result<Option<Cow<str>>, ApiError> {
+    let Some(body) = body else {
+        return Ok(None);
+    };
+    if !is_base64_encoded {
+        if body.len() > max_bytes {
+            return Err(ApiError::PayloadTooLarge);
+        }
+        return Ok(Some(Cow::Borrowed(body)));
+    }
+
+    // every 4 base64 characters are at most 3 bytes, so this is checked
+    // before decoding anything
+    if body.len() / 4 * 3 > max_bytes {
+        return Err(ApiError::PayloadTooLarge);
+    }
+    let invalid = || ApiError::InvalidBody("body is not valid base64 encoded utf-8".to_string());
+    let decoded = STANDARD.decode(body).map_err(|_| invalid())?;
+    if decoded.len() > max_bytes {
+        return Err(ApiError::PayloadTooLarge);
+    }
+    String::from_utf8(decoded)
+        .map(|body| Some(Cow::Owned(body)))
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_bodies_over_the_limit() {
+        assert_eq!(
+            decode_with_limit(Some(r#"{"hp": 50}"#), false, 10),
+            Ok(Some(Cow::Borrowed(r#"{"hp": 50}"#)))
+        );
+        assert_eq!(
+            decode_with_limit(Some(r#"{"hp": 100}"#), false, 10),
+            Err(ApiError::PayloadTooLarge)
+        );
+        // `{"hp": 50}` is 10 bytes
+        let encoded = STANDARD.encode(r#"{"hp": 50}"#);
+        assert_eq!(
+            decode_with_limit(Some(&encoded), true, 10)
+                .unwrap()
+                .unwrap(),
+            r#"{"hp": 50}"#
+        );
+        assert_eq!(
+            decode_with_limit(Some(&encoded), true, 9),
+            Err(ApiError::PayloadTooLarge)
+        );
+        assert!(matches!(
+            decode_with_limit(Some("not base64!"), true, 100),
+            Err(ApiError::InvalidBody(_))
+        ));
+        assert_eq!(decode_with_limit(None, true, 0), Ok(None));
+    }
+}
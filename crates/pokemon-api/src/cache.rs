@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    env,
+    num::NonZeroUsize,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+
+use crate::metrics;
+
+/// Responses for `/api/pokemon/:slug`, kept for as long as this lambda
+/// instance stays warm. `RESPONSE_CACHE_SIZE` sets how many are kept; unset
+/// or `0` turns the cache off. The dataset only changes on deploy, which
+/// starts fresh instances, so only admin corrections invalidate anything, and
+/// only in the instance that made them.
+static RESPONSE_CACHE: Lazy<Option<ResponseCache>> = Lazy::new(|| {
+    env::var("RESPONSE_CACHE_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .and_then(NonZeroUsize::new)
+        .map(ResponseCache::new)
+});
+
+/// How long after a correction reads of that pokemon go to the primary, so a
+/// replica that hasn't caught up yet can't put the old row back in the cache.
+const REPLICA_LAG_ALLOWANCE: Duration = Duration::from_secs(30);
+
+pub fn response_cache() -> Option<&'static ResponseCache> {
+    RESPONSE_CACHE.as_ref()
+}
+
+/// A least recently used cache of whole responses. Lookups are quick and
+/// never held across an `.await`, so a plain `Mutex` is enough; a
+/// `RwLock` wouldn't help since reads reorder the LRU list too.
+#[derive(Debug)]
+pub struct ResponseCache {
+    responses: Mutex<LruCache<String, ApiGatewayProxyResponse>>,
+    /// When each slug was last forgotten, for `recently_forgotten`.
+    forgotten: Mutex<HashMap<String, Instant>>,
+}
+
+impl ResponseCache {
+    pub fn new(size: NonZeroUsize) -> Self {
+        Self {
+            responses: Mutex::new(LruCache::new(size)),
+            forgotten: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached response for `key`, recording a hit or a miss.
+    pub fn get(&self, key: &str) -> Option<ApiGatewayProxyResponse> {
+        let response = self.lock().get(key).cloned();
+        metrics::record_cache_lookup(response.is_some());
+        response
+    }
+
+    /// Caches `response` under `key`, evicting the least recently used
+    /// response when full.
+    pub fn put(&self, key: String, response: &ApiGatewayProxyResponse) {
+        self.lock().put(key, response.clone());
+    }
+
+    /// Drops every cached response for `slug`, in any language, case or
+    /// format.
+    pub fn forget(&self, slug: &str) {
+        let prefix = format!("{slug}:");
+        let mut responses = self.lock();
+        let keys: Vec<_> = responses
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for key in keys {
+            responses.pop(&key);
+        }
+        drop(responses);
+
+        let mut forgotten = unpoisoned(&self.forgotten);
+        forgotten.retain(|_, at| at.elapsed() < REPLICA_LAG_ALLOWANCE);
+        forgotten.insert(slug.to_string(), Instant::now());
+    }
+
+    /// Whether `slug` was forgotten so recently that a replica may still
+    /// hold the row from before the correction.
+    pub fn recently_forgotten(&self, slug: &str) -> bool {
+        unpoisoned(&self.forgotten)
+            .get(slug)
+            .is_some_and(|at| at.elapsed() < REPLICA_LAG_ALLOWANCE)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LruCache<String, ApiGatewayProxyResponse>> {
+        unpoisoned(&self.responses)
+    }
+}
+
+fn unpoisoned<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // a panic mid-lookup can't leave the cache half updated, so keep using it
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status_code: i64) -> ApiGatewayProxyResponse {
+        crate::response::response(status_code).empty()
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_response() {
+        let cache = ResponseCache::new(NonZeroUsize::new(2).unwrap());
+        cache.put("bulbasaur".to_string(), &response(200));
+        cache.put("ivysaur".to_string(), &response(201));
+        assert!(cache.get("bulbasaur").is_some());
+
+        cache.put("venusaur".to_string(), &response(202));
+        assert!(cache.get("ivysaur").is_none());
+        assert_eq!(cache.get("bulbasaur").unwrap().status_code, 200);
+        assert_eq!(cache.get("venusaur").unwrap().status_code, 202);
+
+        cache.forget("venusaur");
+        assert!(cache.get("venusaur").is_none());
+        assert!(cache.get("bulbasaur").is_some());
+    }
+
+    #[test]
+    fn remembers_what_was_just_forgotten() {
+        let cache = ResponseCache::new(NonZeroUsize::new(2).unwrap());
+        assert!(!cache.recently_forgotten("squirtle"));
+        cache.forget("squirtle");
+        assert!(cache.recently_forgotten("squirtle"));
+        assert!(!cache.recently_forgotten("bulbasaur"));
+    }
+}
@@ -0,0 +1,88 @@
+use serde_json::{Map, Value};
+
+use crate::error::ApiError;
+
+/// Field name casing a client can pick with `?case=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Case {
+    /// `legendary_or_mythical`, as the structs are written.
+    #[default]
+    Snake,
+    /// `legendaryOrMythical`, for front-end clients.
+    Camel,
+}
+
+impl Case {
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        match value {
+            None | Some("snake") => Ok(Case::Snake),
+            Some("camel") => Ok(Case::Camel),
+            Some(other) => Err(ApiError::InvalidQuery(format!(
+                "case must be one of `snake` or `camel`, got `{other}`"
+            ))),
+        }
+    }
+
+    /// Renames every object key in `value`, nested ones included.
+    pub fn apply(self, value: Value) -> Value {
+        match self {
+            Case::Snake => value,
+            Case::Camel => camel_case_keys(value),
+        }
+    }
+}
+
+fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (to_camel_case(&key), camel_case_keys(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(camel_case_keys).collect()),
+        value => value,
+    }
+}
+
+fn to_camel_case(snake: &str) -> String {
+    let mut camel = String::with_capacity(snake.len());
+    let mut upper_next = false;
+    for c in snake.chars() {
+        if c == '_' {
+            upper_next = !camel.is_empty();
+        } else if upper_next {
+            camel.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn camel_cases_nested_keys_only_when_asked() {
+        let value = json!({
+            "legendary_or_mythical": true,
+            "hp": 106,
+            "effectiveness": [{ "fly_attack_effectiveness": 0.5 }]
+        });
+
+        assert_eq!(Case::Snake.apply(value.clone()), value);
+        assert_eq!(
+            Case::Camel.apply(value),
+            json!({
+                "legendaryOrMythical": true,
+                "hp": 106,
+                "effectiveness": [{ "flyAttackEffectiveness": 0.5 }]
+            })
+        );
+        assert!(Case::parse(Some("kebab")).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::env;
+
+use http::header::{HeaderMap, HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN, VARY};
+
+/// The request headers browsers may send cross-origin beyond the safelisted
+/// ones: the admin token, JSON bodies and conditional range requests.
+pub const ALLOWED_HEADERS: &str = "authorization, content-type, if-modified-since";
+
+/// The origins browsers may call the API from, from `CORS_ALLOWED_ORIGINS`.
+///
+/// A matching `Origin` is echoed back rather than answering with `*`, so the
+/// list can be locked down to the front ends that actually use the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowedOrigins(Vec<String>);
+
+impl AllowedOrigins {
+    /// Parses a comma separated list like `https://a.example,https://b.example`.
+    /// Each entry must be a bare `scheme://host[:port]` origin, the form
+    /// browsers send, so a typo fails startup instead of silently never
+    /// matching.
+    pub fn parse(origins: &str) -> Result<Self, String> {
+        let origins = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| {
+                let host = origin
+                    .strip_prefix("https://")
+                    .or_else(|| origin.strip_prefix("http://"));
+                match host {
+                    Some(host) if !host.is_empty() && !host.contains('/') => {
+                        Ok(origin.to_ascii_lowercase())
+                    }
+                    _ => Err(format!(
+                        "CORS_ALLOWED_ORIGINS entries must look like `https://example.com`, got `{origin}`"
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if origins.is_empty() {
+            return Err("CORS_ALLOWED_ORIGINS is set but lists no origins".to_string());
+        }
+        Ok(AllowedOrigins(origins))
+    }
+
+    /// CORS is off, and no headers are added, when the variable is unset.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        env::var("CORS_ALLOWED_ORIGINS")
+            .ok()
+            .map(|origins| AllowedOrigins::parse(&origins))
+            .transpose()
+    }
+
+    /// The headers to add to the response to a request with these headers.
+    /// `Vary: Origin` goes on every response, since whether the allow header
+    /// is there depends on who asked.
+    pub fn response_headers(&self, request_headers: &HeaderMap) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(VARY, HeaderValue::from_static("origin"));
+        let origin = request_headers
+            .get(ORIGIN)
+            .filter(|origin| {
+                origin.to_str().is_ok_and(|origin| {
+                    self.0
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+                })
+            })
+            .cloned();
+        if let Some(origin) = origin {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_anything_but_bare_origins() {
+        assert!(AllowedOrigins::parse("https://pokedex.example, http://localhost:3000").is_ok());
+        assert!(AllowedOrigins::parse("*").is_err());
+        assert!(AllowedOrigins::parse("https://pokedex.example/").is_err());
+        assert!(AllowedOrigins::parse("pokedex.example").is_err());
+        assert!(AllowedOrigins::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn echoes_only_listed_origins() {
+        let origins = AllowedOrigins::parse("https://pokedex.example").unwrap();
+        let request = |origin: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ORIGIN, HeaderValue::from_static(origin));
+            origins.response_headers(&headers)
+        };
+
+        let allowed = request("https://Pokedex.example");
+        assert_eq!(
+            allowed[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://Pokedex.example"
+        );
+        assert_eq!(allowed[VARY], "origin");
+
+        let denied = request("https://evil.example");
+        assert!(denied.get(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        assert_eq!(denied[VARY], "origin");
+    }
+}
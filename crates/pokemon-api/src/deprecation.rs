@@ -0,0 +1,93 @@
+use std::env;
+
+use http::header::{HeaderName, HeaderValue};
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+use crate::response::ResponseBuilder;
+
+static LEGACY_SHAPE: Lazy<Option<Deprecation>> = Lazy::new(|| {
+    Deprecation::new(
+        env::var("DEPRECATE_LEGACY_SHAPE").ok().as_deref(),
+        env::var("LEGACY_SHAPE_SUNSET").ok().as_deref(),
+    )
+});
+
+/// The notice to put on the trimmed hp-only shape `/api/pokemon/:slug`
+/// answers with by default. Off unless `DEPRECATE_LEGACY_SHAPE` is `1` or
+/// `true`; `LEGACY_SHAPE_SUNSET`, an HTTP date, adds when it goes away.
+pub fn legacy_shape() -> Option<&'static Deprecation> {
+    LEGACY_SHAPE.as_ref()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deprecation {
+    sunset: Option<HeaderValue>,
+}
+
+impl Deprecation {
+    fn new(enabled: Option<&str>, sunset: Option<&str>) -> Option<Self> {
+        if !matches!(enabled, Some("1" | "true")) {
+            return None;
+        }
+        let sunset = sunset
+            .filter(|sunset| {
+                let valid = httpdate::parse_http_date(sunset).is_ok();
+                if !valid {
+                    warn!(
+                        sunset,
+                        "LEGACY_SHAPE_SUNSET is not an HTTP date, leaving it out"
+                    );
+                }
+                valid
+            })
+            .and_then(|sunset| HeaderValue::from_str(sunset).ok());
+        Some(Deprecation { sunset })
+    }
+
+    /// `Deprecation`, and `Sunset` when there is a date.
+    pub fn headers(&self, mut builder: ResponseBuilder) -> ResponseBuilder {
+        builder = builder.header(
+            HeaderName::from_static("deprecation"),
+            HeaderValue::from_static("true"),
+        );
+        if let Some(sunset) = &self.sunset {
+            builder = builder.header(HeaderName::from_static("sunset"), sunset.clone());
+        }
+        builder
+    }
+
+    /// Where to get the full pokemon instead, for the response body.
+    pub fn hint(&self, slug: &str) -> String {
+        format!(
+            "this trimmed shape is deprecated, request /api/pokemon?slugs={slug} \
+            for every field or ?fields=id for just the id"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::response;
+
+    #[test]
+    fn only_enabled_explicitly_and_keeps_valid_sunsets() {
+        assert_eq!(Deprecation::new(None, None), None);
+        assert_eq!(Deprecation::new(Some("false"), None), None);
+        assert_eq!(
+            Deprecation::new(Some("true"), Some("next tuesday")),
+            Some(Deprecation { sunset: None })
+        );
+
+        let deprecation =
+            Deprecation::new(Some("1"), Some("Sun, 01 Mar 2026 00:00:00 GMT")).unwrap();
+        let headers = deprecation
+            .headers(response(200))
+            .json(&())
+            .unwrap()
+            .headers;
+        assert_eq!(headers["deprecation"], "true");
+        assert_eq!(headers["sunset"], "Sun, 01 Mar 2026 00:00:00 GMT");
+    }
+}
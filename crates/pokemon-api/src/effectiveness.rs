@@ -0,0 +1,181 @@
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use upload_pokemon_data::{Effectiveness, PokemonTableRow, PokemonType};
+
+use crate::error::ApiError;
+
+/// How the eighteen `*_attack_effectiveness` columns are laid out in a full
+/// pokemon response, picked with `?effectiveness=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EffectivenessFormat {
+    /// The columns as they are in the table.
+    #[default]
+    Raw,
+    /// A single `effectiveness` object keyed by attacking type.
+    Map,
+    /// Both of the above.
+    Both,
+    /// An `effectiveness` object of the attacking types at each multiplier
+    /// other than 1, `{"4x": ["rock"], "0x": ["ground"], ...}`, the way
+    /// damage calculators lay matchups out.
+    Grouped,
+}
+
+impl EffectivenessFormat {
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        match value {
+            None | Some("raw") => Ok(EffectivenessFormat::Raw),
+            Some("map") => Ok(EffectivenessFormat::Map),
+            Some("both") => Ok(EffectivenessFormat::Both),
+            Some("grouped") => Ok(EffectivenessFormat::Grouped),
+            Some(other) => Err(ApiError::InvalidQuery(format!(
+                "effectiveness must be one of `raw`, `map`, `both` or `grouped`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// A full pokemon laid out according to an `EffectivenessFormat`. A shaped
+/// body keeps the row it was built from.
+#[derive(Debug)]
+pub enum PokemonBody<'a> {
+    Raw(&'a PokemonTableRow),
+    Shaped(&'a PokemonTableRow, Value),
+}
+
+impl Serialize for PokemonBody<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PokemonBody::Raw(row) => row.serialize(serializer),
+            PokemonBody::Shaped(_, body) => body.serialize(serializer),
+        }
+    }
+}
+
+impl<'a> PokemonBody<'a> {
+    pub fn new(
+        row: &'a PokemonTableRow,
+        format: EffectivenessFormat,
+    ) -> Result<Self, serde_json::Error> {
+        if format == EffectivenessFormat::Raw {
+            return Ok(PokemonBody::Raw(row));
+        }
+
+        let mut value = serde_json::to_value(row)?;
+        if let Value::Object(fields) = &mut value {
+            if format != EffectivenessFormat::Both {
+                fields.retain(|field, _| !field.ends_with("_attack_effectiveness"));
+            }
+            let effectiveness = if format == EffectivenessFormat::Grouped {
+                serde_json::to_value(grouped(row))?
+            } else {
+                let map: BTreeMap<_, _> = row
+                    .effectiveness_map()
+                    .into_iter()
+                    .map(|(attacking, multiplier)| (attacking, Effectiveness(multiplier)))
+                    .collect();
+                serde_json::to_value(map)?
+            };
+            fields.insert("effectiveness".to_string(), effectiveness);
+        }
+        Ok(PokemonBody::Shaped(row, value))
+    }
+
+    pub fn row(&self) -> &'a PokemonTableRow {
+        match *self {
+            PokemonBody::Raw(row) | PokemonBody::Shaped(row, _) => row,
+        }
+    }
+}
+
+/// The attacking types at each multiplier, keyed like `"0.5x"`, leaving out
+/// the neutral ones.
+fn grouped(row: &PokemonTableRow) -> BTreeMap<String, Vec<PokemonType>> {
+    let mut groups: BTreeMap<String, Vec<PokemonType>> = BTreeMap::new();
+    for (attacking, multiplier) in row.effectiveness_map() {
+        if multiplier == 1.0 {
+            continue;
+        }
+        // rounded like `Effectiveness`, and `2x` rather than `2.0x`, but
+        // formatted here so `?numeric=string` can't quote it
+        let label = (f64::from(multiplier) * 1000.0).round() / 1000.0;
+        groups
+            .entry(format!("{label}x"))
+            .or_default()
+            .push(attacking);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_options_and_defaults_to_raw() {
+        assert_eq!(
+            EffectivenessFormat::parse(None),
+            Ok(EffectivenessFormat::Raw)
+        );
+        assert_eq!(
+            EffectivenessFormat::parse(Some("map")),
+            Ok(EffectivenessFormat::Map)
+        );
+        assert_eq!(
+            EffectivenessFormat::parse(Some("both")),
+            Ok(EffectivenessFormat::Both)
+        );
+        assert!(EffectivenessFormat::parse(Some("table")).is_err());
+    }
+
+    #[test]
+    fn groups_a_dual_type_by_multiplier() {
+        // fire/flying, so rock hits it 4x and ground not at all
+        let charizard = PokemonTableRow {
+            slug: "charizard".to_string(),
+            fire_attack_effectiveness: 0.5,
+            water_attack_effectiveness: 2.0,
+            electric_attack_effectiveness: 2.0,
+            grass_attack_effectiveness: 0.25,
+            fighting_attack_effectiveness: 0.5,
+            ground_attack_effectiveness: 0.0,
+            bug_attack_effectiveness: 0.25,
+            rock_attack_effectiveness: 4.0,
+            steel_attack_effectiveness: 0.5,
+            fairy_attack_effectiveness: 0.5,
+            ..Default::default()
+        };
+
+        let PokemonBody::Shaped(_, body) =
+            PokemonBody::new(&charizard, EffectivenessFormat::Grouped).unwrap()
+        else {
+            panic!("expected a shaped body");
+        };
+        assert_eq!(
+            body["effectiveness"],
+            serde_json::json!({
+                "4x": ["rock"],
+                "2x": ["water", "electric"],
+                "0.5x": ["fire", "fighting", "steel", "fairy"],
+                "0.25x": ["grass", "bug"],
+                "0x": ["ground"]
+            })
+        );
+        assert!(body.get("rock_attack_effectiveness").is_none());
+    }
+
+    #[tokio::test]
+    async fn group_labels_ignore_the_float_format() {
+        // every other multiplier defaults to 0
+        let squirtle = PokemonTableRow {
+            grass_attack_effectiveness: 2.0,
+            fire_attack_effectiveness: 0.5,
+            ..Default::default()
+        };
+        let labels: Vec<_> = upload_pokemon_data::FloatFormat::String
+            .scope(async { grouped(&squirtle).into_keys().collect() })
+            .await;
+        assert_eq!(labels, ["0.5x", "0x", "2x"]);
+    }
+}
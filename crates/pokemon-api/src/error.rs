@@ -0,0 +1,143 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use serde_json::{json, Value};
+
+use crate::response::response;
+
+/// Everything the API can answer with other than a success.
+///
+/// Error bodies look like `{ "error": "<message>", "code": "<CODE>" }`. The
+/// `code` is stable and meant for clients to branch on, the message is not.
+///
+/// | code                 | status |
+/// |----------------------|--------|
+/// | `INVALID_SLUG`       | 400    |
+/// | `INVALID_QUERY`      | 400    |
+/// | `INVALID_BODY`       | 400    |
+/// | `UNAUTHORIZED`       | 401    |
+/// | `POKEMON_MOVED`      | 301    |
+/// | `POKEMON_NOT_FOUND`  | 404    |
+/// | `TYPE_NOT_FOUND`     | 404    |
+/// | `ROUTE_NOT_FOUND`    | 404    |
+/// | `METHOD_NOT_ALLOWED` | 405    |
+/// | `POKEMON_GONE`       | 410    |
+/// | `PAYLOAD_TOO_LARGE`  | 413    |
+/// | `RATE_LIMITED`       | 429    |
+/// | `SERVER_BUSY`        | 429    |
+/// | `RESPONSE_TOO_LARGE` | 500    |
+/// | `INTERNAL_ERROR`     | 500    |
+/// | `DB_UNAVAILABLE`     | 503    |
+/// | `MAINTENANCE`        | 503    |
+/// | `QUERY_TIMEOUT`      | 504    |
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    InvalidSlug(&'static str),
+    InvalidQuery(String),
+    InvalidBody(String),
+    Unauthorized,
+    PokemonMoved,
+    PokemonNotFound,
+    TypeNotFound,
+    RouteNotFound,
+    MethodNotAllowed,
+    PokemonGone,
+    PayloadTooLarge,
+    RateLimited,
+    ServerBusy,
+    ResponseTooLarge,
+    Internal,
+    DbUnavailable,
+    Maintenance,
+    QueryTimeout,
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidSlug(_) => "INVALID_SLUG",
+            ApiError::InvalidQuery(_) => "INVALID_QUERY",
+            ApiError::InvalidBody(_) => "INVALID_BODY",
+            ApiError::Unauthorized => "UNAUTHORIZED",
+            ApiError::PokemonMoved => "POKEMON_MOVED",
+            ApiError::PokemonNotFound => "POKEMON_NOT_FOUND",
+            ApiError::TypeNotFound => "TYPE_NOT_FOUND",
+            ApiError::RouteNotFound => "ROUTE_NOT_FOUND",
+            ApiError::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+            ApiError::PokemonGone => "POKEMON_GONE",
+            ApiError::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ApiError::RateLimited => "RATE_LIMITED",
+            ApiError::ServerBusy => "SERVER_BUSY",
+            ApiError::ResponseTooLarge => "RESPONSE_TOO_LARGE",
+            ApiError::Internal => "INTERNAL_ERROR",
+            ApiError::DbUnavailable => "DB_UNAVAILABLE",
+            ApiError::Maintenance => "MAINTENANCE",
+            ApiError::QueryTimeout => "QUERY_TIMEOUT",
+        }
+    }
+
+    pub fn status_code(&self) -> i64 {
+        match self {
+            ApiError::InvalidSlug(_) | ApiError::InvalidQuery(_) | ApiError::InvalidBody(_) => 400,
+            ApiError::Unauthorized => 401,
+            ApiError::PokemonMoved => 301,
+            ApiError::PokemonNotFound | ApiError::TypeNotFound | ApiError::RouteNotFound => 404,
+            ApiError::MethodNotAllowed => 405,
+            ApiError::PokemonGone => 410,
+            ApiError::PayloadTooLarge => 413,
+            ApiError::RateLimited | ApiError::ServerBusy => 429,
+            ApiError::ResponseTooLarge | ApiError::Internal => 500,
+            ApiError::DbUnavailable | ApiError::Maintenance => 503,
+            ApiError::QueryTimeout => 504,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::InvalidSlug(message) => message,
+            ApiError::InvalidQuery(message) | ApiError::InvalidBody(message) => message,
+            ApiError::Unauthorized => "missing or wrong admin token",
+            ApiError::PokemonMoved => "pokemon has moved",
+            ApiError::PokemonNotFound => "pokemon not found",
+            ApiError::TypeNotFound => "type not found",
+            ApiError::RouteNotFound => "no such route",
+            ApiError::MethodNotAllowed => "method not allowed",
+            ApiError::PokemonGone => "pokemon has been removed",
+            ApiError::PayloadTooLarge => "request body is too large",
+            ApiError::RateLimited => "rate limit exceeded",
+            ApiError::ServerBusy => "too many requests in flight, retry shortly",
+            ApiError::ResponseTooLarge => "response is too large, ask for fewer pokemon",
+            ApiError::Internal => "internal server error",
+            ApiError::DbUnavailable => "database is unavailable",
+            ApiError::Maintenance => "down for maintenance",
+            ApiError::QueryTimeout => "query took too long",
+        }
+    }
+
+    pub fn body(&self) -> Value {
+        json!({
+            "error": self.message(),
+            "code": self.code(),
+        })
+    }
+
+    pub fn into_response(self) -> Result<ApiGatewayProxyResponse, Error> {
+        response(self.status_code()).json(&self.body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_carries_message_and_code() {
+        assert_eq!(
+            ApiError::PokemonNotFound.body(),
+            json!({ "error": "pokemon not found", "code": "POKEMON_NOT_FOUND" })
+        );
+        assert_eq!(
+            ApiError::InvalidSlug("searched for empty pokemon").body(),
+            json!({ "error": "searched for empty pokemon", "code": "INVALID_SLUG" })
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use pokemon_api::queries::{self, PokemonTable};
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+use upload_pokemon_data::PokemonId;
+
+use crate::error::ApiError;
+
+/// Related data a client can ask `/api/pokemon/:slug` to nest with
+/// `?expand=abilities,types,evolution`. Each one is another query, so none
+/// are run unless asked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Expand {
+    abilities: bool,
+    types: bool,
+    evolution: bool,
+}
+
+impl Expand {
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        let mut expand = Expand::default();
+        for relation in value.unwrap_or_default().split(',').map(str::trim) {
+            match relation {
+                "" => {}
+                "abilities" => expand.abilities = true,
+                "types" => expand.types = true,
+                "evolution" => expand.evolution = true,
+                other => {
+                    return Err(ApiError::InvalidQuery(format!(
+                        "expand must be a list of `abilities`, `types` or `evolution`, \
+                        got `{other}`"
+                    )))
+                }
+            }
+        }
+        Ok(expand)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Expand::default()
+    }
+
+    /// Adds each asked for relation of the pokemon with `id` to `body`.
+    pub async fn inline(
+        self,
+        pool: &MySqlPool,
+        table: PokemonTable,
+        id: &PokemonId,
+        body: &mut Value,
+    ) -> Result<(), sqlx::Error> {
+        if self.abilities {
+            body["abilities"] = json!(queries::abilities(pool, id).await?);
+        }
+        if self.types {
+            body["types"] = json!(queries::types(pool, id).await?);
+        }
+        if self.evolution {
+            body["evolution"] = json!(queries::evolution(pool, table, id).await?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_relations_only() {
+        assert!(Expand::parse(None).unwrap().is_empty());
+        assert_eq!(
+            Expand::parse(Some("types, evolution")).unwrap(),
+            Expand {
+                abilities: false,
+                types: true,
+                evolution: true,
+            }
+        );
+        assert!(matches!(
+            Expand::parse(Some("types,moves")),
+            Err(ApiError::InvalidQuery(message)) if message.ends_with("got `moves`")
+        ));
+    }
+}
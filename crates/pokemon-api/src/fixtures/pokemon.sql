@@ -0,0 +1,56 @@
+-- ho-oh, squirtle and bulbasaur as they are in pokemon.csv
+INSERT INTO pokemon (
+    id,
+    slug,
+    name,
+    pokedex_id,
+    hp,
+    attack,
+    defense,
+    special_attack,
+    special_defense,
+    speed,
+    height,
+    weight,
+    generation,
+    female_rate,
+    genderless,
+    legendary_or_mythical,
+    is_default,
+    forms_switchable,
+    base_experience,
+    capture_rate,
+    base_happiness,
+    primary_color,
+    number_pokemon_with_typing,
+    normal_attack_effectiveness,
+    fire_attack_effectiveness,
+    water_attack_effectiveness,
+    electric_attack_effectiveness,
+    grass_attack_effectiveness,
+    ice_attack_effectiveness,
+    fighting_attack_effectiveness,
+    poison_attack_effectiveness,
+    ground_attack_effectiveness,
+    fly_attack_effectiveness,
+    psychic_attack_effectiveness,
+    bug_attack_effectiveness,
+    rock_attack_effectiveness,
+    ghost_attack_effectiveness,
+    dragon_attack_effectiveness,
+    dark_attack_effectiveness,
+    steel_attack_effectiveness,
+    fairy_attack_effectiveness
+) VALUES
+    ('0ujsszwN8NRY24YaXiTIE2VWDTS', 'bulbasaur', 'Bulbasaur', 1, 45, 49, 49, 65, 65, 45, 7, 69, 1, 0.125, FALSE, FALSE, TRUE, FALSE, 64, 45, 70, 'green', 15.0, 1.0, 2.0, 0.5, 0.5, 0.25, 2.0, 0.5, 1.0, 1.0, 2.0, 2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5),
+    ('0ujsswThIGTUYm2K8FjOOfXtY1K', 'squirtle', 'Squirtle', 7, 44, 48, 65, 50, 64, 43, 5, 90, 1, 0.125, FALSE, FALSE, TRUE, FALSE, 63, 45, 70, 'blue', 72.0, 1.0, 0.5, 0.5, 2.0, 2.0, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 1.0),
+    ('0ujssxh0cECutqzMgbtXSGnjorm', 'ho-oh', 'Ho Oh', 250, 106, 130, 90, 110, 154, 90, 38, 1990, 2, NULL, TRUE, TRUE, TRUE, FALSE, 306, 3, 0, 'red', 7.0, 1.0, 0.5, 2.0, 2.0, 0.25, 1.0, 0.5, 1.0, 0.0, 1.0, 1.0, 0.25, 4.0, 1.0, 1.0, 1.0, 0.5, 0.5);
+
+INSERT INTO typing (id, pokemon_id, typing) VALUES
+    ('0ujtsYcgvSTl8PAuAdqWYSMnLOv', '0ujsszwN8NRY24YaXiTIE2VWDTS', 'Grass'),
+    ('0ujtsYcgvSTl8PAuAdqWYSMnLOw', '0ujsszwN8NRY24YaXiTIE2VWDTS', 'Poison'),
+    ('0ujtsYcgvSTl8PAuAdqWYSMnLOx', '0ujsswThIGTUYm2K8FjOOfXtY1K', 'Water'),
+    ('0ujtsYcgvSTl8PAuAdqWYSMnLOy', '0ujssxh0cECutqzMgbtXSGnjorm', 'Fire'),
+    ('0ujtsYcgvSTl8PAuAdqWYSMnLOz', '0ujssxh0cECutqzMgbtXSGnjorm', 'Flying');
+
+UPDATE pokemon SET name_ja = 'ゼニガメ', name_fr = 'Carapuce' WHERE slug = 'squirtle';
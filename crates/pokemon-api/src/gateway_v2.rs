@@ -0,0 +1,100 @@
+//! HTTP API (payload format 2.0) support, enabled with the `http-api`
+//! feature. Requests are converted to the REST API shape the routes are
+//! written against, and responses converted back.
+
+use aws_lambda_events::event::apigw::{
+    ApiGatewayProxyRequest, ApiGatewayProxyResponse, ApiGatewayV2httpRequest,
+    ApiGatewayV2httpResponse,
+};
+use lambda_runtime::{Error, LambdaEvent};
+
+pub async fn handler(
+    LambdaEvent { payload, context }: LambdaEvent<ApiGatewayV2httpRequest>,
+) -> Result<ApiGatewayV2httpResponse, Error> {
+    let response = crate::handler(LambdaEvent::new(into_rest_request(payload), context)).await?;
+    Ok(into_http_response(response))
+}
+
+/// HTTP APIs send the decoded path on the request context, and the encoded
+/// one as `raw_path`. Both start with the stage name unless the API is on the
+/// `$default` stage, which the routes don't expect, so it's stripped.
+fn into_rest_request(request: ApiGatewayV2httpRequest) -> ApiGatewayProxyRequest {
+    let http = request.request_context.http;
+    let path = http
+        .path
+        .or(request.raw_path)
+        .map(|path| strip_stage(path, request.request_context.stage.as_deref()));
+    let mut rest = ApiGatewayProxyRequest {
+        path,
+        http_method: http.method,
+        headers: request.headers,
+        query_string_parameters: request.query_string_parameters,
+        path_parameters: request.path_parameters,
+        stage_variables: request.stage_variables,
+        body: request.body,
+        is_base64_encoded: request.is_base64_encoded,
+        ..Default::default()
+    };
+    rest.request_context.identity.source_ip = http.source_ip;
+    rest
+}
+
+fn strip_stage(path: String, stage: Option<&str>) -> String {
+    let Some(stage) = stage.filter(|stage| *stage != "$default") else {
+        return path;
+    };
+    match path
+        .strip_prefix('/')
+        .and_then(|path| path.strip_prefix(stage))
+    {
+        Some("") => "/".to_string(),
+        Some(rest) if rest.starts_with('/') => rest.to_string(),
+        _ => path,
+    }
+}
+
+fn into_http_response(response: ApiGatewayProxyResponse) -> ApiGatewayV2httpResponse {
+    ApiGatewayV2httpResponse {
+        status_code: response.status_code,
+        headers: response.headers,
+        multi_value_headers: response.multi_value_headers,
+        body: response.body,
+        is_base64_encoded: response.is_base64_encoded,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_the_path_and_source_ip_from_an_http_api_request() {
+        let mut request = ApiGatewayV2httpRequest::default();
+        request.raw_path = Some("/api/pokemon/ho%2Doh".to_string());
+        request.request_context.http.path = Some("/api/pokemon/ho-oh".to_string());
+        request.request_context.http.source_ip = Some("203.0.113.7".to_string());
+
+        let rest = into_rest_request(request.clone());
+        assert_eq!(rest.path.as_deref(), Some("/api/pokemon/ho-oh"));
+        assert_eq!(
+            rest.request_context.identity.source_ip.as_deref(),
+            Some("203.0.113.7")
+        );
+
+        request.request_context.http.path = None;
+        let rest = into_rest_request(request);
+        assert_eq!(rest.path.as_deref(), Some("/api/pokemon/ho%2Doh"));
+    }
+
+    #[test]
+    fn strips_a_named_stage_from_the_path() {
+        let stage = |path: &str, stage| strip_stage(path.to_string(), stage);
+
+        assert_eq!(stage("/prod/api/pokemon", Some("prod")), "/api/pokemon");
+        assert_eq!(stage("/prod", Some("prod")), "/");
+        assert_eq!(stage("/production/api", Some("prod")), "/production/api");
+        assert_eq!(stage("/api/pokemon", Some("$default")), "/api/pokemon");
+        assert_eq!(stage("/api/pokemon", None), "/api/pokemon");
+    }
+}
@@ -0,0 +1,482 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonHp;
+use serde::{
+    ser::{SerializeMap, SerializeStruct},
+    Serialize, Serializer,
+};
+use upload_pokemon_data::PokemonId;
+
+use crate::{
+    effectiveness::PokemonBody,
+    error::ApiError,
+    response::{Format, ResponseBuilder},
+};
+
+const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// Document shapes a client can pick with `?format=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// Pokemon as the structs serialize them.
+    #[default]
+    Plain,
+    /// A [JSON:API](https://jsonapi.org) document, with each pokemon a
+    /// `pokemon` resource whose `id` is its KSUID.
+    JsonApi,
+}
+
+impl Shape {
+    /// JSON:API is a JSON format, so it can't be combined with asking for
+    /// MessagePack.
+    pub fn parse(value: Option<&str>, format: Format) -> Result<Self, ApiError> {
+        match (value, format) {
+            (None | Some("plain"), _) => Ok(Shape::Plain),
+            (Some("jsonapi"), Format::Json) => Ok(Shape::JsonApi),
+            (Some("jsonapi"), Format::MessagePack) => Err(ApiError::InvalidQuery(
+                "format=jsonapi is only available as JSON, not MessagePack".to_string(),
+            )),
+            (Some(other), _) => Err(ApiError::InvalidQuery(format!(
+                "format must be one of `plain` or `jsonapi`, got `{other}`"
+            ))),
+        }
+    }
+
+    /// Answers with a single pokemon, which is the primary data in JSON:API.
+    pub fn one<T: Identified>(
+        self,
+        builder: ResponseBuilder,
+        format: Format,
+        pokemon: &T,
+    ) -> Result<ApiGatewayProxyResponse, Error> {
+        match self {
+            Shape::Plain => builder.format(format, pokemon),
+            Shape::JsonApi => respond(builder, &Document::new(Resource::of(pokemon))),
+        }
+    }
+
+    /// Answers with a list of pokemon, an array of resource objects in
+    /// JSON:API.
+    pub fn list<T: Identified>(
+        self,
+        builder: ResponseBuilder,
+        format: Format,
+        pokemon: &[T],
+    ) -> Result<ApiGatewayProxyResponse, Error> {
+        match self {
+            Shape::Plain => builder.format(format, pokemon),
+            Shape::JsonApi => respond(builder, &Document::new(resources(pokemon))),
+        }
+    }
+}
+
+/// Answers with `document`, for routes whose JSON:API shape is more than a
+/// list of pokemon.
+pub fn respond<D: Serialize>(
+    builder: ResponseBuilder,
+    document: &Document<D>,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    builder.json_as(MEDIA_TYPE, document)
+}
+
+/// A pokemon body that knows which pokemon it is.
+pub trait Identified: Serialize {
+    fn id(&self) -> &PokemonId;
+}
+
+impl Identified for PokemonBody<'_> {
+    fn id(&self) -> &PokemonId {
+        &self.row().id
+    }
+}
+
+impl Identified for PokemonHp {
+    fn id(&self) -> &PokemonId {
+        &self.id
+    }
+}
+
+/// A top-level document. `meta` is left out when there is none.
+#[derive(Debug, Serialize)]
+pub struct Document<D> {
+    data: D,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<serde_json::Value>,
+}
+
+impl<D> Document<D> {
+    pub fn new(data: D) -> Self {
+        Document { data, meta: None }
+    }
+
+    pub fn meta(mut self, meta: serde_json::Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+}
+
+/// A pokemon as a resource object: `id` beside `type`, and every other field
+/// under `attributes` in the order the body writes them.
+#[derive(Debug, Serialize)]
+pub struct Resource<'a, T: ?Sized> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: &'a PokemonId,
+    attributes: Attributes<'a, T>,
+}
+
+impl<'a, T: Serialize + ?Sized> Resource<'a, T> {
+    /// `body` as the pokemon `id`, for bodies that were reshaped into a
+    /// `Value` after the query.
+    pub fn new(id: &'a PokemonId, body: &'a T) -> Self {
+        Resource {
+            kind: "pokemon",
+            id,
+            attributes: Attributes(body),
+        }
+    }
+}
+
+impl<'a, T: Identified> Resource<'a, T> {
+    pub fn of(pokemon: &'a T) -> Self {
+        Resource::new(pokemon.id(), pokemon)
+    }
+}
+
+/// Each of `pokemon` as a resource object.
+pub fn resources<T: Identified>(pokemon: &[T]) -> Vec<Resource<'_, T>> {
+    pokemon.iter().map(Resource::of).collect()
+}
+
+/// A body without its `id` field.
+#[derive(Debug)]
+struct Attributes<'a, T: ?Sized>(&'a T);
+
+impl<T: Serialize + ?Sized> Serialize for Attributes<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(WithoutId(serializer))
+    }
+}
+
+/// Passes everything through to `S` except the top-level `id` field of a
+/// struct or map. Lengths are passed through as given; JSON ignores them, and
+/// JSON:API is only ever JSON.
+struct WithoutId<S>(S);
+
+impl<S: Serializer> Serializer for WithoutId<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = S::SerializeSeq;
+    type SerializeTuple = S::SerializeTuple;
+    type SerializeTupleStruct = S::SerializeTupleStruct;
+    type SerializeTupleVariant = S::SerializeTupleVariant;
+    type SerializeMap = MapWithoutId<S::SerializeMap>;
+    type SerializeStruct = WithoutId<S::SerializeStruct>;
+    type SerializeStructVariant = S::SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> {
+        self.0.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<S::Ok, S::Error> {
+        self.0.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<S::Ok, S::Error> {
+        self.0.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<S::Ok, S::Error> {
+        self.0.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+        self.0.serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+        self.0.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<S::Ok, S::Error> {
+        self.0.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<S::Ok, S::Error> {
+        self.0.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<S::Ok, S::Error> {
+        self.0.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+        self.0.serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+        self.0.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.0.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.0.serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        self.0.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        self.0.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.0.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.serialize_some(value)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<S::SerializeSeq, S::Error> {
+        self.0.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<S::SerializeTuple, S::Error> {
+        self.0.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeTupleStruct, S::Error> {
+        self.0.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        Ok(MapWithoutId {
+            map: self.0.serialize_map(len)?,
+            skip_value: false,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Ok(WithoutId(self.0.serialize_struct(name, len)?))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, variant_index, variant, len)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+impl<S: SerializeStruct> SerializeStruct for WithoutId<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        if key == "id" {
+            return self.0.skip_field(key);
+        }
+        self.0.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.0.end()
+    }
+}
+
+/// The map half of `WithoutId`. A key can be written apart from its value,
+/// so whether the last key was `id` is remembered until the value comes.
+struct MapWithoutId<M> {
+    map: M,
+    skip_value: bool,
+}
+
+impl<M: SerializeMap> SerializeMap for MapWithoutId<M> {
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), M::Error> {
+        self.skip_value = is_id(key);
+        if self.skip_value {
+            return Ok(());
+        }
+        self.map.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> {
+        if self.skip_value {
+            return Ok(());
+        }
+        self.map.serialize_value(value)
+    }
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        if is_id(key) {
+            return Ok(());
+        }
+        self.map.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<M::Ok, M::Error> {
+        self.map.end()
+    }
+}
+
+fn is_id<T: Serialize + ?Sized>(key: &T) -> bool {
+    serde_json::to_value(key).is_ok_and(|key| key == "id")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use upload_pokemon_data::PokemonTableRow;
+
+    use crate::effectiveness::EffectivenessFormat;
+
+    #[test]
+    fn only_json_can_be_json_api() {
+        assert_eq!(Shape::parse(None, Format::MessagePack), Ok(Shape::Plain));
+        assert_eq!(
+            Shape::parse(Some("jsonapi"), Format::Json),
+            Ok(Shape::JsonApi)
+        );
+        assert!(Shape::parse(Some("jsonapi"), Format::MessagePack).is_err());
+        assert!(Shape::parse(Some("xml"), Format::Json).is_err());
+    }
+
+    #[test]
+    fn attributes_keep_the_field_order() {
+        let squirtle = PokemonHp {
+            id: "0ujsswThIGTUYm2K8FjOOfXtY1K".parse().unwrap(),
+            pokedex_id: 7,
+            name: "Squirtle".to_string(),
+            hp: 44,
+            legendary_or_mythical: false,
+        };
+        assert_eq!(
+            serde_json::to_string(&Document::new(Resource::of(&squirtle))).unwrap(),
+            r#"{"data":{"type":"pokemon","id":"0ujsswThIGTUYm2K8FjOOfXtY1K","attributes":{"pokedex_id":7,"name":"Squirtle","hp":44,"legendary_or_mythical":false}}}"#
+        );
+    }
+
+    #[test]
+    fn collections_are_arrays_of_resources() {
+        let bulbasaur = PokemonTableRow {
+            id: "0ujsszwN8NRY24YaXiTIE2VWDTS".parse().unwrap(),
+            slug: "bulbasaur".to_string(),
+            ..Default::default()
+        };
+        let squirtle = PokemonTableRow {
+            id: "0ujsswThIGTUYm2K8FjOOfXtY1K".parse().unwrap(),
+            slug: "squirtle".to_string(),
+            ..Default::default()
+        };
+        let pokemon = [
+            PokemonBody::new(&bulbasaur, EffectivenessFormat::Raw).unwrap(),
+            PokemonBody::new(&squirtle, EffectivenessFormat::Map).unwrap(),
+        ];
+        let document = serde_json::to_value(Document::new(resources(&pokemon))).unwrap();
+        assert_eq!(document["data"].as_array().unwrap().len(), 2);
+        for (resource, slug) in document["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(["bulbasaur", "squirtle"])
+        {
+            assert_eq!(resource["type"], "pokemon");
+            assert_eq!(resource["attributes"]["slug"], slug);
+            assert!(resource["attributes"].get("id").is_none());
+        }
+        assert_eq!(document["data"][1]["id"], "0ujsswThIGTUYm2K8FjOOfXtY1K");
+        assert!(document["data"][1]["attributes"]["effectiveness"].is_object());
+        assert!(document.get("meta").is_none());
+
+        let lookup =
+            Document::new(resources::<PokemonBody>(&[])).meta(json!({ "missing": ["missingno"] }));
+        assert_eq!(
+            serde_json::to_value(lookup).unwrap(),
+            json!({ "data": [], "meta": { "missing": ["missingno"] } })
+        );
+    }
+}
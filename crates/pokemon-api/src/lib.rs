@@ -0,0 +1,4 @@
+//! The queries behind the lambda's routes, in a library so benchmarks can
+//! call them without going through the handler.
+
+pub mod queries;
@@ -0,0 +1,84 @@
+use std::env;
+
+use once_cell::sync::Lazy;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use pokemon_api::queries::{self, Evolution, PokemonTable};
+use sqlx::MySqlPool;
+use upload_pokemon_data::PokemonId;
+
+/// Whether single pokemon responses carry `Link` headers to their related
+/// resources, from `RELATED_LINK_HEADERS` (`1` or `true`). Off by default,
+/// since finding out which relations exist costs two more queries.
+static RELATED_LINK_HEADERS: Lazy<bool> = Lazy::new(|| {
+    env::var("RELATED_LINK_HEADERS").is_ok_and(|value| matches!(value.as_str(), "1" | "true"))
+});
+
+/// Slugs keep their hyphens.
+const SLUG: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-');
+
+pub fn enabled() -> bool {
+    *RELATED_LINK_HEADERS
+}
+
+/// The `Link` header for the pokemon with `id` at `path`, or `None` when it
+/// has nothing related to link to.
+pub async fn related(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    path: &str,
+    id: &PokemonId,
+) -> Result<Option<String>, sqlx::Error> {
+    let has_abilities = !queries::abilities(pool, id).await?.is_empty();
+    let evolution = queries::evolution(pool, table, id).await?;
+    Ok(link_header(path, has_abilities, &evolution))
+}
+
+fn link_header(path: &str, has_abilities: bool, evolution: &Evolution) -> Option<String> {
+    let mut links = Vec::new();
+    if has_abilities {
+        links.push(format!(r#"<{path}?expand=abilities>; rel="related""#));
+    }
+    let neighbours: Vec<_> = evolution
+        .evolves_from
+        .iter()
+        .chain(&evolution.evolves_into)
+        .collect();
+    if !neighbours.is_empty() {
+        links.push(format!(r#"<{path}?expand=evolution>; rel="related""#));
+    }
+    // siblings of this pokemon's path, so a proxied prefix is kept
+    let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+    for slug in neighbours {
+        links.push(format!(
+            r#"<{parent}/{}>; rel="related""#,
+            utf8_percent_encode(slug, SLUG)
+        ));
+    }
+    (!links.is_empty()).then(|| links.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_abilities_and_evolution_neighbours() {
+        let evolution = Evolution {
+            evolves_from: Some("bulbasaur".to_string()),
+            evolves_into: vec!["venusaur".to_string()],
+        };
+        assert_eq!(
+            link_header("/api/pokemon/ivysaur", true, &evolution).unwrap(),
+            "</api/pokemon/ivysaur?expand=abilities>; rel=\"related\", \
+            </api/pokemon/ivysaur?expand=evolution>; rel=\"related\", \
+            </api/pokemon/bulbasaur>; rel=\"related\", \
+            </api/pokemon/venusaur>; rel=\"related\""
+        );
+
+        let no_evolution = Evolution {
+            evolves_from: None,
+            evolves_into: Vec::new(),
+        };
+        assert_eq!(link_header("/api/pokemon/mew", false, &no_evolution), None);
+    }
+}
@@ -1,100 +1,705 @@
-use std::env;
+mod body;
+mod cache;
+mod case;
+mod cors;
+mod database;
+mod deprecation;
+mod effectiveness;
+mod error;
+mod expand;
+#[cfg(feature = "http-api")]
+mod gateway_v2;
+mod jsonapi;
+mod links;
+mod maintenance;
+mod metrics;
+mod numeric;
+#[cfg(feature = "otel")]
+mod otel;
+mod rate_limit;
+mod response;
+mod routes;
+mod suggest;
+#[cfg(test)]
+mod test_support;
+mod tombstones;
+mod trace;
+
+use std::{
+    any::Any,
+    borrow::Cow,
+    future::Future,
+    panic::AssertUnwindSafe,
+    str::Utf8Error,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use aws_lambda_events::{
-    encodings::Body,
-    event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse},
+use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
+use case::Case;
+use cors::AllowedOrigins;
+use error::ApiError;
+use expand::Expand;
+use futures::FutureExt;
+use http::{
+    header::{
+        HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ALLOW, LINK,
+        LOCATION, RETRY_AFTER,
+    },
+    Method,
 };
-use http::header::HeaderMap;
+use jsonapi::{Document, Resource, Shape};
 use lambda_runtime::{service_fn, Error, LambdaEvent};
 use once_cell::sync::OnceCell;
-use serde::Serialize;
+use percent_encoding::percent_decode_str;
+use pokemon_api::queries::{self, Lang, PokemonTable};
+use rate_limit::RateLimiter;
+use response::{response, Format};
 use serde_json::json;
-use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
-use tracing::{error, info, instrument};
+use sqlx::{mysql::MySqlPoolOptions, MySql, MySqlPool, Pool};
+use tombstones::find_tombstone;
+use tracing::{error, field, info, instrument, warn, Span};
+#[cfg(not(feature = "otel"))]
 use tracing_subscriber;
-use upload_pokemon_data::PokemonId;
 
 static POOL: OnceCell<Pool<MySql>> = OnceCell::new();
+/// A pool on the read replica at `DATABASE_REPLICA_URL`, when there is one.
+///
+/// Every request but `PATCH` only reads, so it's sent here. Replication is
+/// asynchronous, so a read can briefly miss a correction or upload that has
+/// already committed on the primary; the `PATCH` response itself is read
+/// back from the primary, so the writer always sees their own change.
+static REPLICA_POOL: OnceCell<Option<Pool<MySql>>> = OnceCell::new();
+/// The table every request reads pokemon from, parsed once at startup.
+static POKEMON_TABLE: OnceCell<PokemonTable> = OnceCell::new();
+static POOL_WARM: AtomicBool = AtomicBool::new(false);
+static RATE_LIMITER: OnceCell<Option<RateLimiter>> = OnceCell::new();
+static CORS_ORIGINS: OnceCell<Option<AllowedOrigins>> = OnceCell::new();
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    metrics::init_started();
+    #[cfg(not(feature = "otel"))]
     tracing_subscriber::fmt::init();
+    #[cfg(feature = "otel")]
+    otel::init()?;
+    let cors_origins = AllowedOrigins::from_env()?;
+    let pokemon_table = PokemonTable::from_env()?;
 
-    let database_url = env::var("DATABASE_URL")?;
-    let pool = MySqlPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+    let pool = pool_options()
+        .connect_with(database::connect_options().await?)
         .await?;
+    if database::validate_schema_on_start() {
+        database::validate_schema(&pool).await?;
+    }
+    let replica = match database::replica_connect_options()? {
+        Some(options) => Some(pool_options().connect_with(options).await?),
+        None => None,
+    };
+    let rate_limiter = RATE_LIMITER.get_or_init(RateLimiter::from_env);
+    // one line per cold start saying what's deployed, with no credentials
+    info!(
+        version = env!("CARGO_PKG_VERSION"),
+        http_api = cfg!(feature = "http-api"),
+        otel = cfg!(feature = "otel"),
+        credentials = database::credentials_source(),
+        replica = replica.is_some(),
+        max_connections = MAX_CONNECTIONS,
+        statement_timeout_ms = database::statement_timeout().as_millis() as u64,
+        ?pokemon_table,
+        cors = cors_origins.is_some(),
+        rate_limited = rate_limiter.is_some(),
+        response_cache = cache::response_cache().is_some(),
+        maintenance = maintenance::is_on(),
+        "starting"
+    );
     POOL.get_or_init(|| pool);
-    let processor = service_fn(handler);
+    REPLICA_POOL.get_or_init(|| replica);
+    POKEMON_TABLE.get_or_init(|| pokemon_table);
+    CORS_ORIGINS.get_or_init(|| cors_origins);
+    #[cfg(not(feature = "http-api"))]
+    let processor = service_fn(|event| flushed(handler(event)));
+    #[cfg(feature = "http-api")]
+    let processor = service_fn(|event| flushed(gateway_v2::handler(event)));
     lambda_runtime::run(processor).await?;
     Ok(())
 }
 
-#[derive(Debug, sqlx::FromRow, Serialize)]
-struct PokemonHp {
-    id: PokemonId,
-    name: String,
-    hp: u16,
-    legendary_or_mythical: bool,
+/// Runs one invocation, then with the `otel` feature exports its spans before
+/// the lambda can be frozen.
+async fn flushed<F: Future>(invocation: F) -> F::Output {
+    let output = invocation.await;
+    #[cfg(feature = "otel")]
+    otel::flush().await;
+    output
+}
+
+/// Connections each pool may open.
+const MAX_CONNECTIONS: u32 = 5;
+
+/// Settings shared by the primary and replica pools.
+fn pool_options() -> MySqlPoolOptions {
+    let statement_timeout = database::statement_timeout().as_millis();
+    MySqlPoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                sqlx::query(&format!(
+                    "SET SESSION max_execution_time = {statement_timeout}"
+                ))
+                .execute(conn)
+                .await?;
+                Ok(())
+            })
+        })
+}
+
+fn pool() -> &'static Pool<MySql> {
+    POOL.get().expect("Static pool is not initalized")
+}
+
+fn pokemon_table() -> PokemonTable {
+    *POKEMON_TABLE
+        .get()
+        .expect("Static pokemon table is not initalized")
+}
+
+/// Where reads go: the replica when one is configured, `primary` otherwise.
+fn read_pool(primary: &MySqlPool) -> &MySqlPool {
+    REPLICA_POOL
+        .get()
+        .and_then(Option::as_ref)
+        .unwrap_or(primary)
+}
+
+/// Errors that mean the connection itself went away (e.g. RDS recycled it
+/// while the lambda was frozen), as opposed to errors about the query.
+fn is_connection_error(error: &sqlx::Error) -> bool {
+    matches!(
+        error,
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::Protocol(_)
+    )
 }
 
-#[instrument]
+/// Errors that mean we could not talk to the database at all, answered with
+/// a 503 rather than failing the invocation.
+fn is_unavailable_error(error: &sqlx::Error) -> bool {
+    is_connection_error(error) || matches!(error, sqlx::Error::PoolClosed)
+}
+
+/// Runs the first query after a cold start, retrying it once if the pooled
+/// connection turned out to be dead. Warm invocations run the query as-is.
+async fn retry_once_on_cold_pool<T, F, Fut>(query: F) -> Result<T, sqlx::Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    if POOL_WARM.load(Ordering::Relaxed) {
+        return query().await;
+    }
+    let result = match query().await {
+        Err(error) if is_connection_error(&error) => {
+            warn!(%error, "first query on cold pool hit a dead connection, retrying once");
+            query().await
+        }
+        result => result,
+    };
+    POOL_WARM.store(true, Ordering::Relaxed);
+    result
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        trace_id = field::Empty,
+        path = field::Empty,
+        route = field::Empty,
+        slug = field::Empty,
+        status = field::Empty,
+    )
+)]
 async fn handler(
     LambdaEvent { payload, .. }: LambdaEvent<ApiGatewayProxyRequest>,
 ) -> Result<ApiGatewayProxyResponse, Error> {
+    metrics::record_invocation();
+    let trace_id = trace::trace_id(&payload.headers);
+    Span::current().record("trace_id", trace_id.as_str());
+    if let Some(path) = &payload.path {
+        Span::current().record("path", path.as_str());
+        Span::current().record("route", route_name(path));
+    }
+
+    let cors_headers = CORS_ORIGINS
+        .get()
+        .and_then(Option::as_ref)
+        .map(|origins| origins.response_headers(&payload.headers));
+    let mut response = respond(payload).await?;
+    Span::current().record("status", response.status_code);
+    if let Some(cors_headers) = cors_headers {
+        response.headers.extend(cors_headers);
+    }
+    Ok(response)
+}
+
+/// Everything `handler` answers with, before CORS headers are added.
+async fn respond(payload: ApiGatewayProxyRequest) -> Result<ApiGatewayProxyResponse, Error> {
+    let allowed = allowed_methods(payload.path.as_deref().unwrap_or_default());
+    if payload.http_method == Method::OPTIONS {
+        // CORS preflights land here, the allowed origin is added by `handler`
+        return Ok(response(204)
+            .header(ALLOW, allow_header(allowed))
+            .header(ACCESS_CONTROL_ALLOW_METHODS, allow_header(allowed))
+            .header(
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                HeaderValue::from_static(cors::ALLOWED_HEADERS),
+            )
+            .empty());
+    }
+    if !allowed.contains(&payload.http_method) {
+        warn!(method = %payload.http_method, "method not allowed");
+        let error = ApiError::MethodNotAllowed;
+        return response(error.status_code())
+            .header(ALLOW, allow_header(allowed))
+            .json(&error.body());
+    }
+    if payload.path.as_deref() == Some("/health") {
+        return routes::health(maintenance::is_on(), POOL.get().map(routes::PoolStats::of));
+    }
+    if maintenance::is_on() {
+        return maintenance::unavailable();
+    }
+
+    let source_ip = payload.request_context.identity.source_ip.as_deref();
+    if let (Some(limiter), Some(source_ip)) =
+        (RATE_LIMITER.get().and_then(Option::as_ref), source_ip)
+    {
+        if let Err(retry_after) = limiter.check(source_ip) {
+            warn!(source_ip, "rate limit exceeded");
+            // round up so clients never retry before a token is back
+            let retry_after_secs = retry_after
+                .as_secs()
+                .saturating_add(u64::from(retry_after.subsec_nanos() > 0));
+            let error = ApiError::RateLimited;
+            return response(error.status_code())
+                .header(RETRY_AFTER, HeaderValue::from(retry_after_secs))
+                .json(&error.body());
+        }
+    }
+
+    let float_format = match numeric::parse(payload.query_string_parameters.first("numeric")) {
+        Ok(float_format) => float_format,
+        Err(error) => return error.into_response(),
+    };
+
+    match float_format
+        .scope(route_catching_panics(payload, pool(), pokemon_table()))
+        .await
+    {
+        // every connection is busy: the database is up, we're just overloaded
+        Err(error)
+            if matches!(
+                error.downcast_ref::<sqlx::Error>(),
+                Some(sqlx::Error::PoolTimedOut)
+            ) =>
+        {
+            let retry_after_secs = database::pool_exhausted_retry_after();
+            warn!(%error, retry_after_secs, "connection pool is exhausted");
+            let error = ApiError::ServerBusy;
+            response(error.status_code())
+                .header(RETRY_AFTER, HeaderValue::from(retry_after_secs))
+                .json(&error.body())
+        }
+        Err(error)
+            if error
+                .downcast_ref::<sqlx::Error>()
+                .is_some_and(is_unavailable_error) =>
+        {
+            error!(%error, "database is unavailable");
+            ApiError::DbUnavailable.into_response()
+        }
+        Err(error)
+            if error
+                .downcast_ref::<sqlx::Error>()
+                .is_some_and(database::is_timeout_error) =>
+        {
+            error!(%error, "query hit the statement timeout");
+            ApiError::QueryTimeout.into_response()
+        }
+        result => result,
+    }
+}
+
+/// `path` without a single trailing slash, so `/api/pokemon/` is the
+/// collection like `/api/pokemon`. `/api/pokemon//` still ends in an empty
+/// slug.
+fn normalize_path(path: &str) -> &str {
+    match path.strip_suffix('/') {
+        Some(stripped) if !stripped.is_empty() => stripped,
+        _ => path,
+    }
+}
+
+/// The methods `route` answers on `path`. Everything is read-only except
+/// single pokemon, which admins can `PATCH`.
+fn allowed_methods(path: &str) -> &'static [Method] {
+    match normalize_path(path)
+        .split('/')
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["", "api", "pokemon", "random-team"] => &[Method::GET],
+        ["", "api", "pokemon", slug] if !slug.is_empty() => &[Method::GET, Method::PATCH],
+        _ => &[Method::GET],
+    }
+}
+
+/// The route `path` matches, with its parameters left as placeholders, so
+/// traces group by route rather than by pokemon.
+fn route_name(path: &str) -> &'static str {
+    match normalize_path(path)
+        .split('/')
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["", "health"] => "/health",
+        ["", "admin", "explain"] => "/admin/explain",
+        ["", "api", "fields"] => "/api/fields",
+        ["", "api", "stats", "distribution"] => "/api/stats/distribution",
+        ["", "api", "types", _, "stats"] => "/api/types/:type/stats",
+        ["", "api", "pokemon"] => "/api/pokemon",
+        ["", "api", "pokemon", "random-team"] => "/api/pokemon/random-team",
+        ["", "api", "pokemon", "id", _, "forms"] => "/api/pokemon/id/:pokedex_id/forms",
+        ["", "api", "pokemon", _, "percentiles"] => "/api/pokemon/:slug/percentiles",
+        ["", "api", "pokemon", _, "similar"] => "/api/pokemon/:slug/similar",
+        ["", "api", "pokemon", _] => "/api/pokemon/:slug",
+        _ => "unmatched",
+    }
+}
+
+fn allow_header(methods: &[Method]) -> HeaderValue {
+    let methods: Vec<_> = methods.iter().map(Method::as_str).collect();
+    HeaderValue::try_from(methods.join(", ")).expect("method names are valid header values")
+}
+
+/// `route`, answering a panic anywhere inside it with a logged 500 rather than
+/// letting it take down the whole invocation.
+async fn route_catching_panics(
+    payload: ApiGatewayProxyRequest,
+    primary: &MySqlPool,
+    table: PokemonTable,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    match AssertUnwindSafe(route(payload, primary, table))
+        .catch_unwind()
+        .await
+    {
+        Ok(result) => result,
+        Err(panic) => {
+            error!(panic = panic_message(panic.as_ref()), "handler panicked");
+            ApiError::Internal.into_response()
+        }
+    }
+}
+
+/// The message passed to `panic!`, which is almost always a string.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "non-string panic payload"
+    }
+}
+
+async fn route(
+    payload: ApiGatewayProxyRequest,
+    primary: &MySqlPool,
+    table: PokemonTable,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    // only PATCH writes, everything else can read from the replica
+    let pool = read_pool(primary);
+    let format = Format::from_accept(&payload.headers);
     let path = payload
         .path
+        .as_deref()
+        .map(normalize_path)
         .expect("expect there to always be an event path");
-    let requested_pokemon = path.split("/").last();
-
-    match requested_pokemon {
-        Some("") => {
-            error!("searched for empty pokemon");
-            let error_message = serde_json::to_string(&json!({
-                "error": "searched for empty pokemon"
-            }))?;
-            let response = ApiGatewayProxyResponse {
-                status_code: 400,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(error_message)),
-                is_base64_encoded: false,
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        ["", "api", "pokemon"] => {
+            return routes::pokemon_collection(
+                pool,
+                table,
+                &path,
+                &payload.query_string_parameters,
+                &payload.headers,
+            )
+            .await
+        }
+        ["", "api", "pokemon", "random-team"] => {
+            return routes::random_team(pool, table, &payload.query_string_parameters, format).await
+        }
+        ["", "api", "fields"] => return routes::fields_metadata(format),
+        ["", "api", "types", pokemon_type, "stats"] => {
+            return routes::type_stats(
+                pool,
+                table,
+                pokemon_type,
+                &payload.query_string_parameters,
+                format,
+            )
+            .await
+        }
+        ["", "api", "stats", "distribution"] => {
+            return routes::distribution(pool, table, &payload.query_string_parameters, format)
+                .await
+        }
+        ["", "api", "pokemon", slug]
+            if payload.http_method == Method::PATCH && !slug.is_empty() =>
+        {
+            let body = match body::decode(payload.body.as_deref(), payload.is_base64_encoded) {
+                Ok(body) => body,
+                Err(error) => return error.into_response(),
+            };
+            return match decode_slug(slug) {
+                Ok(slug) => {
+                    // resolved on the primary, like the write itself
+                    let slug = resolve_key(primary, table, slug).await?;
+                    routes::update_pokemon(
+                        primary,
+                        table,
+                        &slug,
+                        body.as_deref(),
+                        &payload.headers,
+                        routes::ADMIN_TOKEN.as_deref(),
+                    )
+                    .await
+                }
+                Err(_) => invalid_slug(slug),
             };
-            Ok(response)
         }
-        None => panic!("requested_pokemon is None, which should never happen"),
-        Some(pokemon_name) => {
-            info!(pokemon_name, "requested a pokemon");
-            let result = sqlx::query_as!(
-                PokemonHp,
-                r#"
-SELECT 
-    id as "id!: PokemonId",
-    name,
-    hp,
-    legendary_or_mythical as "legendary_or_mythical!: bool"
-FROM 
-    pokemon 
-WHERE 
-slug = ?
-"#,
-                pokemon_name
+        ["", "admin", "explain"] => {
+            return routes::explain(
+                pool,
+                table,
+                &payload.query_string_parameters,
+                &payload.headers,
+                routes::ADMIN_TOKEN.as_deref(),
+            )
+            .await
+        }
+        ["", "api", "pokemon", "id", pokedex_id, "forms"] => {
+            return routes::forms(
+                pool,
+                table,
+                pokedex_id,
+                &payload.query_string_parameters,
+                format,
             )
-            .fetch_one(POOL.get().expect("Static pool is not initalized"))
+            .await
+        }
+        ["", "api", "pokemon", slug, "percentiles"] if !slug.is_empty() => {
+            return match decode_slug(slug) {
+                Ok(slug) => {
+                    let slug = resolve_key(pool, table, slug).await?;
+                    routes::percentiles(pool, table, &slug, format).await
+                }
+                Err(_) => invalid_slug(slug),
+            }
+        }
+        ["", "api", "pokemon", slug, "similar"] if !slug.is_empty() => {
+            return match decode_slug(slug) {
+                Ok(slug) => {
+                    let slug = resolve_key(pool, table, slug).await?;
+                    routes::similar(pool, table, &slug, &payload.query_string_parameters, format)
+                        .await
+                }
+                Err(_) => invalid_slug(slug),
+            }
+        }
+        _ => {}
+    }
+    // only a non-empty segment straight after `/api/pokemon/` is a slug
+    let segment = match path.split('/').collect::<Vec<_>>().as_slice() {
+        ["", "api", "pokemon", ""] => {
+            error!("searched for empty pokemon");
+            return ApiError::InvalidSlug("searched for empty pokemon").into_response();
+        }
+        ["", "api", "pokemon", segment] => *segment,
+        _ => {
+            warn!(path, "no route for path");
+            return ApiError::RouteNotFound.into_response();
+        }
+    };
+    let Ok(pokemon_name) = decode_slug(segment) else {
+        return invalid_slug(segment);
+    };
+    let pokemon_name = resolve_key(pool, table, pokemon_name).await?;
+    let pokemon_name = pokemon_name.as_ref();
+    Span::current().record("slug", pokemon_name);
+    match payload.query_string_parameters.first("fields") {
+        None => {}
+        Some("id") => return routes::pokemon_id(pool, table, pokemon_name, format).await,
+        Some(_) => {
+            return ApiError::InvalidQuery("fields must be `id`".to_string()).into_response()
+        }
+    }
+    let case = match Case::parse(payload.query_string_parameters.first("case")) {
+        Ok(case) => case,
+        Err(error) => return error.into_response(),
+    };
+    let lang = match parse_lang(payload.query_string_parameters.first("lang")) {
+        Ok(lang) => lang,
+        Err(error) => return error.into_response(),
+    };
+    let expand = match Expand::parse(payload.query_string_parameters.first("expand")) {
+        Ok(expand) => expand,
+        Err(error) => return error.into_response(),
+    };
+    let shape = match Shape::parse(payload.query_string_parameters.first("format"), format) {
+        Ok(shape) => shape,
+        Err(error) => return error.into_response(),
+    };
+    info!(
+        pokemon_name,
+        lang = lang.code(),
+        ?expand,
+        "requested a pokemon"
+    );
+    let cache = cache::response_cache();
+    // a replica may not have a just-corrected row yet, and caching what it
+    // answers would keep serving the old one after the correction
+    let pool = match cache {
+        Some(cache) if cache.recently_forgotten(pokemon_name) => primary,
+        _ => pool,
+    };
+    let cache_key = format!(
+        "{pokemon_name}:{}:{case:?}:{format:?}:{expand:?}:{shape:?}",
+        lang.code()
+    );
+    if let Some(cached) = cache.and_then(|cache| cache.get(&cache_key)) {
+        return Ok(cached);
+    }
+    let result =
+        retry_once_on_cold_pool(|| queries::pokemon_by_slug_in(pool, table, pokemon_name, lang))
             .await?;
 
-            let json_pokemon = serde_json::to_string(&result)?;
-            let response = ApiGatewayProxyResponse {
-                status_code: 200,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(json_pokemon)),
-                is_base64_encoded: false,
-            };
-            Ok(response)
+    let Some(result) = result else {
+        return pokemon_not_found(pool, table, pokemon_name).await;
+    };
+
+    let deprecation = deprecation::legacy_shape();
+    let mut builder = response(200);
+    if let Some(deprecation) = deprecation {
+        builder = deprecation.headers(builder);
+    }
+    if links::enabled() {
+        if let Some(links) = links::related(pool, table, &path, &result.id).await? {
+            builder = builder.header(LINK, HeaderValue::try_from(links)?);
         }
     }
+    let response = match (case, deprecation) {
+        (Case::Snake, None) if expand.is_empty() => shape.one(builder, format, &result)?,
+        _ => {
+            let mut body = serde_json::to_value(&result)?;
+            expand.inline(pool, table, &result.id, &mut body).await?;
+            if let Some(deprecation) = deprecation {
+                body["deprecation"] = json!(deprecation.hint(pokemon_name));
+            }
+            let body = case.apply(body);
+            match shape {
+                Shape::Plain => builder.format(format, &body)?,
+                Shape::JsonApi => {
+                    jsonapi::respond(builder, &Document::new(Resource::new(&result.id, &body)))?
+                }
+            }
+        }
+    };
+    // too large and other error responses aren't worth keeping
+    if let (Some(cache), 200) = (cache, response.status_code) {
+        cache.put(cache_key, &response);
+    }
+    Ok(response)
+}
+
+fn parse_lang(code: Option<&str>) -> Result<Lang, ApiError> {
+    let Some(code) = code else {
+        return Ok(Lang::En);
+    };
+    Lang::parse(code).ok_or_else(|| {
+        let supported: Vec<_> = Lang::ALL
+            .iter()
+            .map(|lang| format!("`{}`", lang.code()))
+            .collect();
+        ApiError::InvalidQuery(format!(
+            "lang must be one of {}, got `{code}`",
+            supported.join(", ")
+        ))
+    })
+}
+
+/// API Gateway may hand us the path segment still percent-encoded.
+fn decode_slug(segment: &str) -> Result<Cow<str>, Utf8Error> {
+    percent_decode_str(segment).decode_utf8()
+}
+
+/// Pokemon can be looked up by slug or by pokedex id, which means the
+/// default form. No slug is all digits, so a number is always a pokedex id;
+/// one with no pokemon is passed through to 404 like an unknown slug.
+async fn resolve_key<'a>(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    key: Cow<'a, str>,
+) -> Result<Cow<'a, str>, Error> {
+    let Ok(pokedex_id) = key.parse::<u16>() else {
+        return Ok(key);
+    };
+    Ok(
+        queries::default_slug_for_pokedex_id(pool, table, pokedex_id)
+            .await?
+            .map_or(key, Cow::Owned),
+    )
+}
+
+fn invalid_slug(segment: &str) -> Result<ApiGatewayProxyResponse, Error> {
+    error!(segment, "slug is not valid percent-encoded utf-8");
+    ApiError::InvalidSlug("pokemon slug is not valid utf-8").into_response()
+}
+
+/// Answers a slug that matched no pokemon: 301 to the new slug if it was
+/// renamed, 410 if it was removed, and otherwise a 404 suggesting similar
+/// slugs.
+pub(crate) async fn pokemon_not_found(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let (error, location) = match find_tombstone(pool, slug).await? {
+        Some(tombstone) => match tombstone.redirect_to {
+            Some(redirect_to) => (
+                ApiError::PokemonMoved,
+                Some(format!("/api/pokemon/{redirect_to}")),
+            ),
+            None => (ApiError::PokemonGone, None),
+        },
+        None => (ApiError::PokemonNotFound, None),
+    };
+    info!(
+        slug,
+        code = error.code(),
+        "requested pokemon does not exist"
+    );
+
+    let mut body = error.body();
+    if error == ApiError::PokemonNotFound {
+        let slug = suggest::normalize_slug(slug);
+        body["suggestions"] = json!(suggest::suggest_slugs(pool, table, &slug).await?);
+        body["slug"] = json!(slug);
+    }
+
+    let mut builder = response(error.status_code());
+    if let Some(location) = location {
+        builder = builder.header(LOCATION, HeaderValue::try_from(location)?);
+    }
+    builder.json(&body)
 }
 
 #[cfg(test)]
@@ -105,10 +710,11 @@ mod tests {
         event::apigw::{ApiGatewayProxyRequestContext, ApiGatewayRequestIdentity},
         query_map::QueryMap,
     };
-    use http::Method;
-    use lambda_runtime::Context;
+    use http::header::HeaderMap;
+    use serde_json::json;
 
     use super::*;
+    use crate::test_support::{body_json, db_test};
 
     fn pokemon_event_with_path(path: String) -> ApiGatewayProxyRequest {
         ApiGatewayProxyRequest {
@@ -158,116 +764,372 @@ mod tests {
         }
     }
 
-    async fn setup_db() {
-        let database_url = env::var("DATABASE_URL").unwrap();
-        let pool = MySqlPoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
+    /// For tests that are answered before any query runs.
+    fn unused_pool() -> MySqlPool {
+        MySqlPoolOptions::new()
+            .connect_lazy("mysql://localhost/unused")
+            .unwrap()
+    }
+
+    db_test! {
+        async fn handler_handles_ho_oh(pool: MySqlPool) {
+            let event = pokemon_event_with_path("/api/pokemon/ho-oh".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+
+            assert_eq!(response.status_code, 200);
+            assert_eq!(
+                body_json(response),
+                json!({
+                    "id": "0ujssxh0cECutqzMgbtXSGnjorm",
+                    "name": "Ho Oh",
+                    "hp": 106,
+                    "legendary_or_mythical": true
+                })
+            );
+
+            let event = pokemon_event_with_path("/api/pokemon/ho%2Doh".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+            assert_eq!(response.status_code, 200);
+
+            let mut event = pokemon_event_with_path("/api/pokemon/ho-oh".to_string());
+            event.query_string_parameters =
+                QueryMap::from(HashMap::from([("case".to_string(), "camel".to_string())]));
+            let body = body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap());
+            assert_eq!(body["legendaryOrMythical"], true);
+            assert!(body.get("legendary_or_mythical").is_none());
+        }
+    }
+
+    db_test! {
+        async fn handler_handles_squirtle(pool: MySqlPool) {
+            let event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+
+            assert_eq!(response.status_code, 200);
+            assert_eq!(
+                body_json(response),
+                json!({
+                    "id": "0ujsswThIGTUYm2K8FjOOfXtY1K",
+                    "name": "Squirtle",
+                    "hp": 44,
+                    "legendary_or_mythical": false
+                })
+            );
+
+            let event = pokemon_event_with_path("/api/pokemon/7".to_string());
+            let body = body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap());
+            assert_eq!(body["name"], "Squirtle");
+            assert_eq!(body["pokedex_id"], 7);
+            assert_eq!(body["id"], "0ujsswThIGTUYm2K8FjOOfXtY1K");
+
+            let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+            event.query_string_parameters =
+                QueryMap::from(HashMap::from([("fields".to_string(), "id".to_string())]));
+            assert_eq!(
+                body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap()),
+                json!({ "id": "0ujsswThIGTUYm2K8FjOOfXtY1K" })
+            );
+        }
+    }
+
+    db_test! {
+        async fn handler_handles_bulbasaur(pool: MySqlPool) {
+            let event = pokemon_event_with_path("/api/pokemon/bulbasaur".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+
+            assert_eq!(response.status_code, 200);
+            assert_eq!(
+                body_json(response),
+                json!({
+                    "id": "0ujsszwN8NRY24YaXiTIE2VWDTS",
+                    "name": "Bulbasaur",
+                    "hp": 45,
+                    "legendary_or_mythical": false
+                })
+            );
+
+            let mut event = pokemon_event_with_path("/api/pokemon/bulbasaur".to_string());
+            event.query_string_parameters = QueryMap::from(HashMap::from([(
+                "expand".to_string(),
+                "types,evolution".to_string(),
+            )]));
+            let body = body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap());
+            assert_eq!(body["types"], json!(["Grass", "Poison"]));
+            assert_eq!(
+                body["evolution"],
+                json!({ "evolves_from": null, "evolves_into": [] })
+            );
+            assert!(body.get("abilities").is_none());
+        }
+    }
+
+    db_test! {
+        async fn handler_answers_404_with_suggestions(pool: MySqlPool) {
+            let event = pokemon_event_with_path("/api/pokemon/Squirtel".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+
+            assert_eq!(response.status_code, 404);
+            assert_eq!(
+                body_json(response),
+                json!({
+                    "error": "pokemon not found",
+                    "code": "POKEMON_NOT_FOUND",
+                    "slug": "squirtel",
+                    "suggestions": ["squirtle"]
+                })
+            );
+
+            let event = pokemon_event_with_path("/api/pokemon/9999".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+            assert_eq!(response.status_code, 404);
+
+            // a database from before tombstones still answers 404, not 500
+            sqlx::query("DROP TABLE tombstones")
+                .execute(&pool)
+                .await
+                .unwrap();
+            let event = pokemon_event_with_path("/api/pokemon/Squirtel".to_string());
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+            assert_eq!(response.status_code, 404);
+        }
+    }
+
+    db_test! {
+        async fn handler_answers_with_localized_names(pool: MySqlPool) {
+            let name = |lang: &str| {
+                let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+                event.query_string_parameters =
+                    QueryMap::from(HashMap::from([("lang".to_string(), lang.to_string())]));
+                let pool = pool.clone();
+                async move {
+                    let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+                    (response.status_code, body_json(response)["name"].clone())
+                }
+            };
+
+            assert_eq!(name("ja").await, (200, json!("ゼニガメ")));
+            // no German name in the fixture, so it falls back to English
+            assert_eq!(name("de").await, (200, json!("Squirtle")));
+            assert_eq!(name("xx").await.0, 400);
+        }
+    }
+
+    db_test! {
+        async fn handler_answers_json_api_documents(pool: MySqlPool) {
+            let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+            event.query_string_parameters = QueryMap::from(HashMap::from([(
+                "format".to_string(),
+                "jsonapi".to_string(),
+            )]));
+            let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+            assert_eq!(
+                response.headers[http::header::CONTENT_TYPE],
+                "application/vnd.api+json"
+            );
+            let body = body_json(response);
+            assert_eq!(body["data"]["type"], "pokemon");
+            assert_eq!(body["data"]["id"], "0ujsswThIGTUYm2K8FjOOfXtY1K");
+            assert_eq!(body["data"]["attributes"]["hp"], 44);
+            assert!(body["data"]["attributes"].get("id").is_none());
+
+            let mut event = pokemon_event_with_path("/api/pokemon".to_string());
+            event.query_string_parameters = QueryMap::from(HashMap::from([
+                ("format".to_string(), "jsonapi".to_string()),
+                ("pokedex_from".to_string(), "1".to_string()),
+                ("pokedex_to".to_string(), "7".to_string()),
+            ]));
+            let body = body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap());
+            let slugs: Vec<_> = body["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pokemon| pokemon["attributes"]["slug"].clone())
+                .collect();
+            assert_eq!(slugs, [json!("bulbasaur"), json!("squirtle")]);
+
+            let mut event = pokemon_event_with_path("/api/pokemon/random-team".to_string());
+            event.query_string_parameters = QueryMap::from(HashMap::from([(
+                "format".to_string(),
+                "jsonapi".to_string(),
+            )]));
+            let body = body_json(route(event, &pool, PokemonTable::Pokemon).await.unwrap());
+            let team = body["data"].as_array().unwrap();
+            assert!(!team.is_empty());
+            assert!(team.iter().all(|pokemon| pokemon["type"] == "pokemon"));
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_before_parsing() {
+        let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+        event.http_method = Method::PATCH;
+        event.body = Some(format!(r#"{{"name": "{}"}}"#, "a".repeat(100 * 1024)));
+
+        let response = route(event, &unused_pool(), PokemonTable::Pokemon)
             .await
             .unwrap();
-        POOL.get_or_init(|| pool);
+        assert_eq!(response.status_code, 413);
+        assert_eq!(body_json(response)["code"], "PAYLOAD_TOO_LARGE");
     }
 
     #[tokio::test]
-    async fn handler_handles_ho_oh() {
-        setup_db().await;
-        let event = pokemon_event_with_path("/api/pokemon/ho-oh".to_string());
+    async fn unsupported_methods_are_told_what_is_allowed() {
+        let mut event = pokemon_event_with_path("/api/fields".to_string());
+        event.http_method = Method::DELETE;
+        let response = respond(event).await.unwrap();
+        assert_eq!(response.status_code, 405);
+        assert_eq!(response.headers[ALLOW], "GET");
+        assert_eq!(body_json(response)["code"], "METHOD_NOT_ALLOWED");
 
-        assert_eq!(
-            handler(LambdaEvent::new(event.clone(), Context::default()))
-                .await
-                .unwrap(),
-            ApiGatewayProxyResponse {
-                status_code: 200,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(
-                    serde_json::to_string(&PokemonHp {
-                        name: String::from("Ho Oh"),
-                        hp: 106,
-                        legendary_or_mythical: true
-                    },)
-                    .unwrap()
-                )),
-                is_base64_encoded: false,
-            }
-        )
+        let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+        event.http_method = Method::DELETE;
+        let response = respond(event).await.unwrap();
+        assert_eq!(response.status_code, 405);
+        assert_eq!(response.headers[ALLOW], "GET, PATCH");
     }
 
     #[tokio::test]
-    async fn handler_handles_squirtle() {
-        setup_db().await;
-        let event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+    async fn preflights_list_the_allowed_methods_and_headers() {
+        let mut event = pokemon_event_with_path("/api/pokemon/squirtle".to_string());
+        event.http_method = Method::OPTIONS;
+        let response = respond(event).await.unwrap();
+        assert_eq!(response.status_code, 204);
+        assert_eq!(response.headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, PATCH");
+        assert_eq!(
+            response.headers[ACCESS_CONTROL_ALLOW_HEADERS],
+            "authorization, content-type, if-modified-since"
+        );
+    }
 
-        let response = handler(LambdaEvent::new(event.clone(), Context::default()))
+    #[tokio::test]
+    async fn a_panicking_route_answers_500() {
+        // `route` expects every event to have a path
+        let mut event = pokemon_event_with_path(String::new());
+        event.path = None;
+        let response = route_catching_panics(event, &unused_pool(), PokemonTable::Pokemon)
             .await
             .unwrap();
 
+        assert_eq!(response.status_code, 500);
         assert_eq!(
-            response,
-            ApiGatewayProxyResponse {
-                status_code: 200,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(
-                    serde_json::to_string(&PokemonHp {
-                        name: String::from("Squirtle"),
-                        hp: 44,
-                        legendary_or_mythical: false
-                    },)
-                    .unwrap()
-                )),
-                is_base64_encoded: false,
-            }
-        )
+            body_json(response),
+            json!({ "error": "internal server error", "code": "INTERNAL_ERROR" })
+        );
+    }
+
+    #[test]
+    fn only_connection_errors_are_retried() {
+        assert!(is_connection_error(&sqlx::Error::Io(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_connection_error(&sqlx::Error::RowNotFound));
+    }
+
+    #[test]
+    fn decodes_percent_encoded_slugs() {
+        assert_eq!(decode_slug("ho%2Doh").unwrap(), "ho-oh");
+        assert_eq!(decode_slug("mr%20mime").unwrap(), "mr mime");
+        assert_eq!(decode_slug("squirtle").unwrap(), "squirtle");
+        assert!(decode_slug("%FF%FE").is_err());
     }
 
     #[tokio::test]
-    async fn handler_handles_bulbasaur() {
-        setup_db().await;
-        let event = pokemon_event_with_path("/api/pokemon/bulbasaur".to_string());
+    async fn handler_rejects_undecodable_slug() {
+        let event = pokemon_event_with_path("/api/pokemon/%FF%FE".to_string());
 
         assert_eq!(
-            handler(LambdaEvent::new(event.clone(), Context::default()))
+            route(event, &unused_pool(), PokemonTable::Pokemon)
                 .await
                 .unwrap(),
-            ApiGatewayProxyResponse {
-                status_code: 200,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(
-                    serde_json::to_string(&PokemonHp {
-                        name: String::from("Bulbasaur"),
-                        hp: 45,
-                        legendary_or_mythical: false
-                    },)
-                    .unwrap()
-                )),
-                is_base64_encoded: false,
-            }
+            ApiError::InvalidSlug("pokemon slug is not valid utf-8")
+                .into_response()
+                .unwrap()
         )
     }
 
+    db_test! {
+        async fn trailing_slashes_and_slugs_route_by_segment(pool: MySqlPool) {
+            for path in ["/api/pokemon", "/api/pokemon/"] {
+                let mut event = pokemon_event_with_path(path.to_string());
+                event.query_string_parameters = QueryMap::from(HashMap::from([
+                    ("pokedex_from".to_string(), "1".to_string()),
+                    ("pokedex_to".to_string(), "251".to_string()),
+                ]));
+                let response = route(event, &pool, PokemonTable::Pokemon).await.unwrap();
+                assert_eq!(response.status_code, 200, "{path}");
+                assert_eq!(
+                    body_json(response).as_array().map(Vec::len),
+                    Some(3),
+                    "{path}"
+                );
+            }
+
+            for path in ["/api/pokemon/squirtle", "/api/pokemon/squirtle/"] {
+                let response = route(
+                    pokemon_event_with_path(path.to_string()),
+                    &pool,
+                    PokemonTable::Pokemon,
+                )
+                .await
+                .unwrap();
+                assert_eq!(body_json(response)["name"], "Squirtle", "{path}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn only_segments_after_api_pokemon_are_slugs() {
+        for path in ["/api/pikachu", "/pikachu", "/api/pokemon/pikachu/moves"] {
+            let response = route(
+                pokemon_event_with_path(path.to_string()),
+                &unused_pool(),
+                PokemonTable::Pokemon,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status_code, 404, "{path}");
+            assert_eq!(body_json(response)["code"], "ROUTE_NOT_FOUND", "{path}");
+        }
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path("/api/pokemon//"), "/api/pokemon/");
+    }
+
+    #[test]
+    fn spans_name_the_route_not_the_pokemon() {
+        assert_eq!(route_name("/api/pokemon/ho-oh"), "/api/pokemon/:slug");
+        assert_eq!(route_name("/api/pokemon/ho-oh/"), "/api/pokemon/:slug");
+        assert_eq!(
+            route_name("/api/pokemon/ho-oh/similar"),
+            "/api/pokemon/:slug/similar"
+        );
+        assert_eq!(
+            route_name("/api/pokemon/random-team"),
+            "/api/pokemon/random-team"
+        );
+        assert_eq!(
+            route_name("/api/types/fire/stats"),
+            "/api/types/:type/stats"
+        );
+        assert_eq!(route_name("/pikachu"), "unmatched");
+    }
+
     #[tokio::test]
     async fn handler_handles_empty_pokemon() {
         let event = pokemon_event_with_path("/api/pokemon//".to_string());
 
+        let response = route(event, &unused_pool(), PokemonTable::Pokemon)
+            .await
+            .unwrap();
+        // error bodies are JSON, so clients shouldn't have to sniff them
         assert_eq!(
-            handler(LambdaEvent::new(event.clone(), Context::default()))
-                .await
-                .unwrap(),
-            ApiGatewayProxyResponse {
-                status_code: 400,
-                headers: HeaderMap::new(),
-                multi_value_headers: HeaderMap::new(),
-                body: Some(Body::Text(
-                    serde_json::to_string(&json!({
-                        "error": "searched for empty pokemon"
-                    }))
-                    .unwrap()
-                )),
-                is_base64_encoded: false,
-            }
+            response.headers[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+        assert_eq!(
+            response,
+            ApiError::InvalidSlug("searched for empty pokemon")
+                .into_response()
+                .unwrap()
         )
     }
 }
@@ -0,0 +1,54 @@
+use std::env;
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use http::header::{HeaderValue, RETRY_AFTER};
+use lambda_runtime::Error;
+use once_cell::sync::Lazy;
+
+use crate::{error::ApiError, response::response};
+
+/// Whether `MAINTENANCE_MODE` is `true` (or `1`). It's read once per lambda
+/// instance, so flipping it applies from the next cold start; publishing a
+/// new version with the variable changed is the quickest way to get there.
+static MAINTENANCE_MODE: Lazy<bool> = Lazy::new(|| {
+    env::var("MAINTENANCE_MODE").is_ok_and(|value| matches!(value.as_str(), "1" | "true"))
+});
+
+/// How long clients are asked to wait, from `MAINTENANCE_RETRY_AFTER_SECS`.
+/// Defaults to five minutes.
+static RETRY_AFTER_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("MAINTENANCE_RETRY_AFTER_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(300)
+});
+
+pub fn is_on() -> bool {
+    *MAINTENANCE_MODE
+}
+
+/// What every route but `/health` answers with during maintenance.
+pub fn unavailable() -> Result<ApiGatewayProxyResponse, Error> {
+    let error = ApiError::Maintenance;
+    let mut body = error.body();
+    body["status"] = "maintenance".into();
+    response(error.status_code())
+        .header(RETRY_AFTER, HeaderValue::from(*RETRY_AFTER_SECS))
+        .json(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::body_json;
+
+    #[test]
+    fn maintenance_is_a_503_with_retry_after() {
+        let response = unavailable().unwrap();
+        assert_eq!(response.status_code, 503);
+        assert_eq!(response.headers[RETRY_AFTER], "300");
+        let body = body_json(response);
+        assert_eq!(body["status"], "maintenance");
+        assert_eq!(body["code"], "MAINTENANCE");
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+static INIT_STARTED: OnceCell<Instant> = OnceCell::new();
+static COLD: AtomicBool = AtomicBool::new(true);
+
+/// Call first thing in `main`, so the first invocation can report how long
+/// the lambda took to get ready.
+pub fn init_started() {
+    INIT_STARTED.get_or_init(Instant::now);
+}
+
+/// Prints a `ColdStart` metric in CloudWatch embedded metric format: `1` with
+/// the init duration for the first invocation of this instance, `0` after.
+pub fn record_invocation() {
+    let init_duration = if COLD.swap(false, Ordering::Relaxed) {
+        INIT_STARTED.get().map(Instant::elapsed)
+    } else {
+        None
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    println!("{}", cold_start_metric(timestamp, init_duration));
+}
+
+fn cold_start_metric(timestamp: Duration, init_duration: Option<Duration>) -> Value {
+    let mut metrics = vec![json!({ "Name": "ColdStart", "Unit": "Count" })];
+    let mut metric = json!({
+        "service": "pokemon-api",
+        "ColdStart": u8::from(init_duration.is_some()),
+    });
+    if let Some(init_duration) = init_duration {
+        metrics.push(json!({ "Name": "InitDuration", "Unit": "Milliseconds" }));
+        metric["InitDuration"] = json!(init_duration.as_secs_f64() * 1000.0);
+    }
+    metric["_aws"] = json!({
+        "Timestamp": timestamp.as_millis() as u64,
+        "CloudWatchMetrics": [{
+            "Namespace": "pokemon-api",
+            "Dimensions": [["service"]],
+            "Metrics": metrics,
+        }],
+    });
+    metric
+}
+
+/// Prints a `ResponseCacheHit` metric: `1` when a response came from the
+/// cache, `0` when it had to be built. The average is the hit rate.
+pub fn record_cache_lookup(hit: bool) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    println!("{}", cache_lookup_metric(timestamp, hit));
+}
+
+fn cache_lookup_metric(timestamp: Duration, hit: bool) -> Value {
+    json!({
+        "service": "pokemon-api",
+        "ResponseCacheHit": u8::from(hit),
+        "_aws": {
+            "Timestamp": timestamp.as_millis() as u64,
+            "CloudWatchMetrics": [{
+                "Namespace": "pokemon-api",
+                "Dimensions": [["service"]],
+                "Metrics": [{ "Name": "ResponseCacheHit", "Unit": "Count" }],
+            }],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_cold_starts_report_an_init_duration() {
+        let timestamp = Duration::from_millis(1_690_000_000_000);
+
+        let cold = cold_start_metric(timestamp, Some(Duration::from_millis(250)));
+        assert_eq!(cold["ColdStart"], 1);
+        assert_eq!(cold["InitDuration"], 250.0);
+        assert_eq!(cold["_aws"]["Timestamp"], 1_690_000_000_000u64);
+        assert_eq!(
+            cold["_aws"]["CloudWatchMetrics"][0]["Metrics"][1]["Name"],
+            "InitDuration"
+        );
+
+        let warm = cold_start_metric(timestamp, None);
+        assert_eq!(warm["ColdStart"], 0);
+        assert!(warm.get("InitDuration").is_none());
+    }
+
+    #[test]
+    fn cache_lookups_count_hits() {
+        let timestamp = Duration::from_millis(1_690_000_000_000);
+        assert_eq!(cache_lookup_metric(timestamp, true)["ResponseCacheHit"], 1);
+        assert_eq!(cache_lookup_metric(timestamp, false)["ResponseCacheHit"], 0);
+    }
+}
@@ -0,0 +1,27 @@
+use upload_pokemon_data::FloatFormat;
+
+use crate::error::ApiError;
+
+/// How floats like effectiveness multipliers and `female_rate` are written,
+/// from `?numeric=number|string`. Numbers unless asked.
+pub fn parse(value: Option<&str>) -> Result<FloatFormat, ApiError> {
+    match value {
+        None | Some("number") => Ok(FloatFormat::Number),
+        Some("string") => Ok(FloatFormat::String),
+        Some(other) => Err(ApiError::InvalidQuery(format!(
+            "numeric must be one of `number` or `string`, got `{other}`"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_numbers() {
+        assert_eq!(parse(None), Ok(FloatFormat::Number));
+        assert_eq!(parse(Some("string")), Ok(FloatFormat::String));
+        assert!(parse(Some("text")).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! OpenTelemetry export, enabled with the `otel` feature. Spans go to the
+//! OTLP collector at `OTEL_EXPORTER_OTLP_ENDPOINT` (gRPC, e.g.
+//! `http://localhost:4317`); with the variable unset the lambda only logs,
+//! as it does without the feature. The service name comes from
+//! `OTEL_SERVICE_NAME`.
+
+use std::env;
+
+use lambda_runtime::Error;
+use once_cell::sync::OnceCell;
+use opentelemetry::sdk::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use tracing::warn;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Kept so each invocation's spans can be flushed before the lambda freezes.
+static PROVIDER: OnceCell<TracerProvider> = OnceCell::new();
+
+/// Logs like `tracing_subscriber::fmt::init`, and exports spans when a
+/// collector is configured.
+pub fn init() -> Result<(), Error> {
+    let logs = tracing_subscriber::fmt::layer();
+    let Ok(endpoint) = env::var("OTEL_EXPORTER_OTLP_ENDPOINT") else {
+        tracing_subscriber::registry()
+            .with(logs)
+            .with(LevelFilter::INFO)
+            .init();
+        return Ok(());
+    };
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)?;
+    if let Some(provider) = tracer.provider() {
+        PROVIDER.get_or_init(|| provider);
+    }
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .with(LevelFilter::INFO)
+        .init();
+    Ok(())
+}
+
+/// Exports every finished span. The batch exporter would otherwise send them
+/// on a timer, which doesn't run while the lambda is frozen between
+/// invocations.
+pub async fn flush() {
+    let Some(provider) = PROVIDER.get().cloned() else {
+        return;
+    };
+    // `force_flush` blocks until the collector has answered
+    match tokio::task::spawn_blocking(move || provider.force_flush()).await {
+        Ok(results) => {
+            for error in results.into_iter().filter_map(Result::err) {
+                warn!(%error, "failed to export spans");
+            }
+        }
+        Err(error) => warn!(%error, "span export panicked"),
+    }
+}
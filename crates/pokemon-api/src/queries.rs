@@ -0,0 +1,273 @@
+use serde::Serialize;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::instrument;
+use upload_pokemon_data::{PokemonId, PokemonTableRow};
+pub use upload_pokemon_data::{PokemonTable, POKEMON_COLUMNS};
+
+/// What `/api/pokemon/:slug` answers with.
+///
+/// Responses carry both identifiers. `slug` and `pokedex_id` stay the same
+/// across reloads of the dataset; `id` is a fresh KSUID on every load unless
+/// the uploader derives ids with `PokemonId::from_parts`, so it's only good
+/// for as long as the current load.
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct PokemonHp {
+    pub id: PokemonId,
+    pub pokedex_id: u16,
+    pub name: String,
+    pub hp: u16,
+    pub legendary_or_mythical: bool,
+}
+
+/// Selected beside `POKEMON_COLUMNS` by lists that stop at a `LIMIT`: how
+/// many rows matched before the limit cut them short.
+pub const TOTAL_COUNT: &str = "COUNT(*) OVER () AS total_count";
+
+/// A pokemon from a list that selects `TOTAL_COUNT`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct CountedRow {
+    #[sqlx(flatten)]
+    pub pokemon: PokemonTableRow,
+    pub total_count: i64,
+}
+
+/// The pokemon of a limited list, and how many matched in all.
+pub fn split_counted(rows: Vec<CountedRow>) -> (Vec<PokemonTableRow>, u64) {
+    let total = rows.first().map_or(0, |row| row.total_count as u64);
+    (rows.into_iter().map(|row| row.pokemon).collect(), total)
+}
+
+pub async fn pokemon_by_slug(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+) -> Result<Option<PokemonHp>, sqlx::Error> {
+    pokemon_by_slug_in(pool, table, slug, Lang::En).await
+}
+
+/// The slug of the default form with this pokedex id, so pokemon can be
+/// looked up by either stable identifier.
+pub async fn default_slug_for_pokedex_id(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokedex_id: u16,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "SELECT slug FROM {table} WHERE pokedex_id = ? AND is_default"
+    ))
+    .bind(pokedex_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Languages pokemon names can be asked for in. English is the `name`
+/// column, the others are nullable `name_<code>` columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Ja,
+    Fr,
+    De,
+    Es,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 5] = [Lang::En, Lang::Ja, Lang::Fr, Lang::De, Lang::Es];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Ja => "ja",
+            Lang::Fr => "fr",
+            Lang::De => "de",
+            Lang::Es => "es",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        Lang::ALL.into_iter().find(|lang| lang.code() == code)
+    }
+
+    fn name_column(self) -> &'static str {
+        match self {
+            Lang::En => "name",
+            Lang::Ja => "name_ja",
+            Lang::Fr => "name_fr",
+            Lang::De => "name_de",
+            Lang::Es => "name_es",
+        }
+    }
+}
+
+/// The SQL behind `pokemon_by_slug_in`, taking the slug as its one parameter.
+/// `/admin/explain` plans this same statement.
+pub fn pokemon_by_slug_sql(table: PokemonTable, lang: Lang) -> String {
+    let name = match lang {
+        Lang::En => "name".to_string(),
+        lang => format!("COALESCE({}, name) AS name", lang.name_column()),
+    };
+    format!("SELECT id, pokedex_id, {name}, hp, legendary_or_mythical FROM {table} WHERE slug = ?")
+}
+
+/// `pokemon_by_slug` with the name in `lang`, or in English for pokemon the
+/// dataset has no `lang` name for.
+#[instrument(skip(pool), fields(db.system = "mysql"))]
+pub async fn pokemon_by_slug_in(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    lang: Lang,
+) -> Result<Option<PokemonHp>, sqlx::Error> {
+    sqlx::query_as::<_, PokemonHp>(&pokemon_by_slug_sql(table, lang))
+        .bind(slug)
+        .fetch_optional(pool)
+        .await
+}
+
+/// The abilities of the pokemon with `id`, in dataset order.
+pub async fn abilities(pool: &MySqlPool, id: &PokemonId) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT ability FROM abilities WHERE pokemon_id = ? ORDER BY id")
+        .bind(id)
+        .fetch_all(pool)
+        .await
+}
+
+/// The types of the pokemon with `id`, primary type first.
+pub async fn types(pool: &MySqlPool, id: &PokemonId) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT typing FROM typing WHERE pokemon_id = ? ORDER BY id")
+        .bind(id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Where a pokemon sits in its evolution line, by slug.
+#[derive(Debug, Serialize)]
+pub struct Evolution {
+    pub evolves_from: Option<String>,
+    pub evolves_into: Vec<String>,
+}
+
+pub async fn evolution(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    id: &PokemonId,
+) -> Result<Evolution, sqlx::Error> {
+    let evolves_from = sqlx::query_scalar(&format!(
+        "SELECT pokemon.slug FROM evolutions \
+        JOIN {table} AS pokemon ON pokemon.id = evolutions.evolves_from \
+        WHERE evolutions.pokemon_id = ?"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    let evolves_into = sqlx::query_scalar(&format!(
+        "SELECT pokemon.slug FROM evolutions \
+        JOIN {table} AS pokemon ON pokemon.id = evolutions.pokemon_id \
+        WHERE evolutions.evolves_from = ? \
+        ORDER BY pokemon.pokedex_id, pokemon.slug"
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    Ok(Evolution {
+        evolves_from,
+        evolves_into,
+    })
+}
+
+/// Pokemon with a pokedex id in `from..=to`, default forms first. Other forms
+/// are left out unless `include_forms` is set.
+pub async fn pokedex_range(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    from: u16,
+    to: u16,
+    include_forms: bool,
+) -> Result<Vec<PokemonTableRow>, sqlx::Error> {
+    sqlx::query_as::<_, PokemonTableRow>(&format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} \
+        WHERE pokedex_id BETWEEN ? AND ? AND (? OR is_default) \
+        ORDER BY pokedex_id, is_default DESC"
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(include_forms)
+    .fetch_all(pool)
+    .await
+}
+
+/// The pokemon with any of `slugs`, in no particular order.
+pub async fn pokemon_by_slugs(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slugs: &[&str],
+) -> Result<Vec<PokemonTableRow>, sqlx::Error> {
+    let mut query = QueryBuilder::<MySql>::new(format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} WHERE slug IN ("
+    ));
+    let mut separated = query.separated(", ");
+    for slug in slugs {
+        separated.push_bind(*slug);
+    }
+    separated.push_unseparated(")");
+
+    query
+        .build_query_as::<PokemonTableRow>()
+        .fetch_all(pool)
+        .await
+}
+
+/// The default forms of any of `pokedex_ids`, in no particular order.
+pub async fn pokemon_by_pokedex_ids(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokedex_ids: &[u16],
+) -> Result<Vec<PokemonTableRow>, sqlx::Error> {
+    let mut query = QueryBuilder::<MySql>::new(format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} WHERE is_default = TRUE AND pokedex_id IN ("
+    ));
+    let mut separated = query.separated(", ");
+    for pokedex_id in pokedex_ids {
+        separated.push_bind(*pokedex_id);
+    }
+    separated.push_unseparated(")");
+
+    query
+        .build_query_as::<PokemonTableRow>()
+        .fetch_all(pool)
+        .await
+}
+
+/// The first `limit` pokemon called `name`, ignoring case, the default form
+/// first. Forms can share a display name, so there may be more than one.
+pub async fn pokemon_by_name(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    name: &str,
+    limit: u32,
+) -> Result<Vec<CountedRow>, sqlx::Error> {
+    sqlx::query_as::<_, CountedRow>(&format!(
+        "SELECT {POKEMON_COLUMNS}, {TOTAL_COUNT} FROM {table} WHERE LOWER(name) = LOWER(?) \
+        ORDER BY is_default DESC, slug LIMIT ?"
+    ))
+    .bind(name)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Every form with this pokedex id, the default form first.
+pub async fn pokemon_forms(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokedex_id: u16,
+) -> Result<Vec<PokemonTableRow>, sqlx::Error> {
+    sqlx::query_as::<_, PokemonTableRow>(&format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} WHERE pokedex_id = ? \
+        ORDER BY is_default DESC, slug"
+    ))
+    .bind(pokedex_id)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Per-instance token bucket limiter keyed on the caller's source ip.
+///
+/// Buckets live in memory, so each warm lambda instance enforces the limit
+/// on its own; it protects the database from a single noisy client rather
+/// than giving an exact global limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, burst: u32) -> Self {
+        RateLimiter {
+            capacity: f64::from(burst.max(1)),
+            refill_per_second: f64::from(requests_per_minute) / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reads `RATE_LIMIT_PER_MINUTE` (and optionally `RATE_LIMIT_BURST`,
+    /// defaulting to the per-minute value). Rate limiting is off when unset
+    /// or `0`, since a bucket that never refills would lock clients out for
+    /// good.
+    pub fn from_env() -> Option<Self> {
+        let per_minute = env::var("RATE_LIMIT_PER_MINUTE")
+            .ok()?
+            .parse()
+            .ok()
+            .filter(|per_minute| *per_minute > 0)?;
+        let burst = env::var("RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|burst| burst.parse().ok())
+            .unwrap_or(per_minute);
+        Some(RateLimiter::new(per_minute, burst))
+    }
+
+    /// Takes a token for `key`, or returns how long until one is available.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        // a full bucket is the same as no bucket, so those are dropped rather
+        // than keeping every client ever seen for the life of the instance
+        buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if self.refill_per_second > 0.0 {
+            let missing = 1.0 - bucket.tokens;
+            Err(Duration::from_secs_f64(missing / self.refill_per_second))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+
+    /// The tokens `bucket` holds at `now`.
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        (bucket.tokens + elapsed.as_secs_f64() * self.refill_per_second).min(self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_ip_separately() {
+        let limiter = RateLimiter::new(60, 2);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("10.0.0.1", now).is_ok());
+        assert!(limiter.check_at("10.0.0.1", now).is_ok());
+        assert_eq!(
+            limiter.check_at("10.0.0.1", now),
+            Err(Duration::from_secs(1))
+        );
+        assert!(limiter.check_at("10.0.0.2", now).is_ok());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = RateLimiter::new(60, 1);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("10.0.0.1", now).is_ok());
+        assert!(limiter.check_at("10.0.0.1", now).is_err());
+        assert!(limiter
+            .check_at("10.0.0.1", now + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn forgets_clients_whose_bucket_has_refilled() {
+        let limiter = RateLimiter::new(60, 1);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("10.0.0.1", now).is_ok());
+        assert!(limiter.check_at("10.0.0.2", now).is_ok());
+        assert!(limiter
+            .check_at("10.0.0.3", now + Duration::from_secs(5))
+            .is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.keys().collect::<Vec<_>>(), ["10.0.0.3"]);
+    }
+}
@@ -0,0 +1,217 @@
+use std::env;
+
+use aws_lambda_events::{encodings::Body, event::apigw::ApiGatewayProxyResponse};
+use http::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use lambda_runtime::Error;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tracing::error;
+
+use crate::error::ApiError;
+
+/// The largest body we'll send, from `MAX_RESPONSE_BYTES`. Defaults to 5MiB,
+/// under the 6MB lambda response limit, which fails opaquely.
+static MAX_BODY_BYTES: Lazy<usize> = Lazy::new(|| {
+    env::var("MAX_RESPONSE_BYTES")
+        .ok()
+        .and_then(|bytes| bytes.parse().ok())
+        .unwrap_or(5 * 1024 * 1024)
+});
+
+/// Starts building a response with the given status code.
+pub fn response(status_code: i64) -> ResponseBuilder {
+    ResponseBuilder {
+        status_code,
+        headers: HeaderMap::new(),
+        max_body_bytes: *MAX_BODY_BYTES,
+    }
+}
+
+/// Body formats a client can pick with the `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    MessagePack,
+}
+
+impl Format {
+    /// MessagePack when the client accepts it, JSON otherwise.
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let accepts_msgpack = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|media_range| media_range.split(';').next())
+            .any(|media_type| {
+                matches!(
+                    media_type.trim(),
+                    "application/msgpack" | "application/x-msgpack"
+                )
+            });
+        if accepts_msgpack {
+            Format::MessagePack
+        } else {
+            Format::Json
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    status_code: i64,
+    headers: HeaderMap,
+    max_body_bytes: usize,
+}
+
+impl ResponseBuilder {
+    #[cfg(test)]
+    fn max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// `Some` error response when a body of `len` bytes is over the cap.
+    fn too_large(&self, len: usize) -> Option<Result<ApiGatewayProxyResponse, Error>> {
+        if len <= self.max_body_bytes {
+            return None;
+        }
+        error!(
+            len,
+            max = self.max_body_bytes,
+            "response body is over the size limit"
+        );
+        Some(ApiError::ResponseTooLarge.into_response())
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<ApiGatewayProxyResponse, Error> {
+        self.json_as("application/json", value)
+    }
+
+    /// JSON under a more specific media type, e.g. `application/vnd.api+json`.
+    pub fn json_as<T: Serialize + ?Sized>(
+        self,
+        content_type: &'static str,
+        value: &T,
+    ) -> Result<ApiGatewayProxyResponse, Error> {
+        let body = serde_json::to_string(value)?;
+        if let Some(too_large) = self.too_large(body.len()) {
+            return too_large;
+        }
+        Ok(self
+            .header(CONTENT_TYPE, HeaderValue::from_static(content_type))
+            .finish(Some(Body::Text(body))))
+    }
+
+    /// MessagePack is binary, so it goes out base64 encoded.
+    pub fn msgpack<T: Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> Result<ApiGatewayProxyResponse, Error> {
+        let body = rmp_serde::to_vec_named(value)?;
+        // measured as sent, after base64
+        if let Some(too_large) = self.too_large(body.len().div_ceil(3) * 4) {
+            return too_large;
+        }
+        let mut response = self
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/msgpack"),
+            )
+            .finish(Some(Body::Binary(body)));
+        response.is_base64_encoded = true;
+        Ok(response)
+    }
+
+    pub fn format<T: Serialize + ?Sized>(
+        self,
+        format: Format,
+        value: &T,
+    ) -> Result<ApiGatewayProxyResponse, Error> {
+        match format {
+            Format::Json => self.json(value),
+            Format::MessagePack => self.msgpack(value),
+        }
+    }
+
+    #[allow(dead_code)] // no plain-text routes yet
+    pub fn text(self, body: impl Into<String>) -> ApiGatewayProxyResponse {
+        self.header(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        )
+        .finish(Some(Body::Text(body.into())))
+    }
+
+    /// A response without a body, e.g. a 304.
+    pub fn empty(self) -> ApiGatewayProxyResponse {
+        self.finish(None)
+    }
+
+    fn finish(self, body: Option<Body>) -> ApiGatewayProxyResponse {
+        ApiGatewayProxyResponse {
+            status_code: self.status_code,
+            headers: self.headers,
+            multi_value_headers: HeaderMap::new(),
+            body,
+            is_base64_encoded: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn negotiates_msgpack_from_accept() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Format::from_accept(&headers), Format::Json);
+
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/json;q=0.5, application/msgpack"),
+        );
+        assert_eq!(Format::from_accept(&headers), Format::MessagePack);
+    }
+
+    #[test]
+    fn msgpack_bodies_are_binary_and_base64_flagged() {
+        let value = json!({ "name": "Squirtle", "hp": 44 });
+        let response = response(200).msgpack(&value).unwrap();
+
+        assert!(response.is_base64_encoded);
+        assert_eq!(
+            response.headers.get(CONTENT_TYPE).unwrap(),
+            "application/msgpack"
+        );
+        match response.body {
+            Some(Body::Binary(bytes)) => assert_eq!(
+                rmp_serde::from_slice::<serde_json::Value>(&bytes).unwrap(),
+                value
+            ),
+            body => panic!("expected a binary body, got {body:?}"),
+        }
+    }
+
+    #[test]
+    fn oversized_bodies_become_an_error() {
+        let value = json!({ "name": "Wailord" });
+
+        let too_large = response(200).max_body_bytes(8).json(&value).unwrap();
+        assert_eq!(too_large.status_code, 500);
+        assert_eq!(
+            too_large.body,
+            Some(Body::Text(ApiError::ResponseTooLarge.body().to_string()))
+        );
+
+        let fits = response(200).max_body_bytes(64).json(&value).unwrap();
+        assert_eq!(fits.status_code, 200);
+    }
+}
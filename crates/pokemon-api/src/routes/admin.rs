@@ -0,0 +1,95 @@
+use std::env;
+
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use http::header::{HeaderMap, AUTHORIZATION};
+use lambda_runtime::Error;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use sqlx::MySqlPool;
+use tracing::{info, warn};
+
+use pokemon_api::queries::{self, Lang, PokemonTable};
+
+use crate::{error::ApiError, response};
+
+/// The token admin requests have to send, from `ADMIN_TOKEN`. The routes take
+/// it as an argument rather than reading it themselves.
+pub static ADMIN_TOKEN: Lazy<Option<String>> = Lazy::new(|| env::var("ADMIN_TOKEN").ok());
+
+/// Admin routes only answer when `ADMIN_TOKEN` is set and the request sends
+/// it as `Authorization: Bearer <token>`.
+pub(super) fn is_admin(headers: &HeaderMap, admin_token: Option<&str>) -> bool {
+    let Some(admin_token) = admin_token.filter(|token| !token.is_empty()) else {
+        return false;
+    };
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), admin_token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// `/admin/explain?slug=pikachu`, the query plan for the single pokemon
+/// lookup, to check it is using the `slug` index.
+pub async fn explain(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    query: &QueryMap,
+    headers: &HeaderMap,
+    admin_token: Option<&str>,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    if !is_admin(headers, admin_token) {
+        warn!("rejected an admin request");
+        return ApiError::Unauthorized.into_response();
+    }
+    let Some(slug) = query.first("slug") else {
+        return ApiError::InvalidQuery("slug is required".to_string()).into_response();
+    };
+    info!(slug, "explaining the pokemon lookup");
+
+    response::response(200).json(&lookup_plan(pool, table, slug).await?)
+}
+
+/// MySQL's plan for `queries::pokemon_by_slug` finding `slug`.
+async fn lookup_plan(pool: &MySqlPool, table: PokemonTable, slug: &str) -> Result<Value, Error> {
+    let sql = queries::pokemon_by_slug_sql(table, Lang::En);
+    let plan: String = sqlx::query_scalar(&format!("EXPLAIN FORMAT=JSON {sql}"))
+        .bind(slug)
+        .fetch_one(pool)
+        .await?;
+    Ok(serde_json::from_str(&plan)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::db_test;
+    use http::header::HeaderValue;
+
+    db_test! {
+        async fn the_lookup_is_planned_on_the_slug_index(pool: MySqlPool) {
+            let plan = lookup_plan(&pool, PokemonTable::Pokemon, "squirtle")
+                .await
+                .unwrap();
+
+            assert_eq!(plan["query_block"]["table"]["table_name"], "pokemon");
+            assert_eq!(plan["query_block"]["table"]["key"], "slug");
+        }
+    }
+
+    #[test]
+    fn admin_needs_a_configured_and_matching_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer hunter2"));
+
+        assert!(is_admin(&headers, Some("hunter2")));
+        assert!(!is_admin(&headers, Some("hunter3")));
+        assert!(!is_admin(&headers, None));
+        assert!(!is_admin(&HeaderMap::new(), Some("hunter2")));
+        assert!(!is_admin(&HeaderMap::new(), Some("")));
+    }
+}
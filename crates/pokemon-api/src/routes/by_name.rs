@@ -0,0 +1,125 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, PokemonTable};
+use sqlx::MySqlPool;
+use tracing::info;
+
+use super::with_total_count;
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+};
+
+/// `/api/pokemon?name=`, for clients that know the display name rather than
+/// the slug. Answers with the default form, or with up to `limit` forms of
+/// that name as a list when forms are included.
+pub async fn by_name(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    name: &str,
+    all_forms: bool,
+    limit: u32,
+    format: Format,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let name = name.trim();
+    if name.is_empty() {
+        return ApiError::InvalidQuery("name must not be empty".to_string()).into_response();
+    }
+    info!(name, all_forms, "requested pokemon by name");
+
+    // the default form sorts first, so it's the only row needed without forms
+    let limit = if all_forms { limit } else { 1 };
+    let (rows, total) =
+        queries::split_counted(queries::pokemon_by_name(pool, table, name, limit).await?);
+    let Some(first) = rows.first() else {
+        return ApiError::PokemonNotFound.into_response();
+    };
+
+    if all_forms {
+        let pokemon = rows
+            .iter()
+            .map(|row| PokemonBody::new(row, effectiveness))
+            .collect::<Result<Vec<_>, _>>()?;
+        let builder = with_total_count(response::response(200), total, pokemon.len());
+        shape.list(builder, format, &pokemon)
+    } else {
+        shape.one(
+            response::response(200),
+            format,
+            &PokemonBody::new(first, effectiveness)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+
+    db_test! {
+        async fn finds_pokemon_by_name_ignoring_case(pool: MySqlPool) {
+            let raw = EffectivenessFormat::Raw;
+
+            let found = by_name(
+                &pool,
+                PokemonTable::Pokemon,
+                "ho oh",
+                false,
+                100,
+                Format::Json,
+                Shape::Plain,
+                raw,
+            )
+            .await
+            .unwrap();
+            assert_eq!(found.status_code, 200);
+            assert_eq!(body_json(found)["slug"], "ho-oh");
+
+            let all = by_name(
+                &pool,
+                PokemonTable::Pokemon,
+                "Squirtle",
+                true,
+                100,
+                Format::Json,
+                Shape::Plain,
+                raw,
+            )
+            .await
+            .unwrap();
+            assert_eq!(body_json(all).as_array().unwrap().len(), 1);
+
+            let missing = by_name(
+                &pool,
+                PokemonTable::Pokemon,
+                "Missingno",
+                false,
+                100,
+                Format::Json,
+                Shape::Plain,
+                raw,
+            )
+            .await
+            .unwrap();
+            assert_eq!(missing.status_code, 404);
+
+            let empty = by_name(
+                &pool,
+                PokemonTable::Pokemon,
+                " ",
+                false,
+                100,
+                Format::Json,
+                Shape::Plain,
+                raw,
+            )
+            .await
+            .unwrap();
+            assert_eq!(empty.status_code, 400);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, CountedRow, TOTAL_COUNT};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::info;
+use upload_pokemon_data::PokemonType;
+
+use super::{matchups::Matchups, with_total_count, PokemonTable, POKEMON_COLUMNS};
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+};
+
+/// Whether a pokemon needs any or all of the requested types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeMatch {
+    Any,
+    All,
+}
+
+#[derive(Debug, PartialEq)]
+struct TypeFilter {
+    types: Vec<PokemonType>,
+    type_match: TypeMatch,
+}
+
+impl TypeFilter {
+    fn parse(types: &str, type_match: Option<&str>) -> Result<TypeFilter, ApiError> {
+        let mut parsed = Vec::new();
+        for name in types
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let pokemon_type: PokemonType = name
+                .parse()
+                .map_err(|error| ApiError::InvalidQuery(error.to_string()))?;
+            if !parsed.contains(&pokemon_type) {
+                parsed.push(pokemon_type);
+            }
+        }
+        if parsed.is_empty() {
+            return Err(ApiError::InvalidQuery(
+                "type must name at least one type".to_string(),
+            ));
+        }
+        let type_match = match type_match {
+            None | Some("any") => TypeMatch::Any,
+            Some("all") => TypeMatch::All,
+            Some(_) => {
+                return Err(ApiError::InvalidQuery(
+                    "match must be one of `any` or `all`".to_string(),
+                ))
+            }
+        };
+
+        Ok(TypeFilter {
+            types: parsed,
+            type_match,
+        })
+    }
+}
+
+/// `/api/pokemon?type=water,flying&match=any|all`, narrowed further by
+/// `matchups` when the query has `weak_to`, `resists` or `immune_to`, and
+/// cut off after `limit` pokemon.
+pub async fn by_type(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    types: &str,
+    type_match: Option<&str>,
+    matchups: Option<&Matchups>,
+    include_forms: bool,
+    limit: u32,
+    format: Format,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let filter = match TypeFilter::parse(types, type_match) {
+        Ok(filter) => filter,
+        Err(error) => return error.into_response(),
+    };
+    info!(?filter, include_forms, "requested pokemon by type");
+
+    let mut query = QueryBuilder::<MySql>::new(format!(
+        "SELECT {POKEMON_COLUMNS}, {TOTAL_COUNT} FROM {table} WHERE ("
+    ));
+    query.push_bind(include_forms).push(
+        " OR is_default) AND id IN (\
+        SELECT pokemon_id FROM typing WHERE typing IN (",
+    );
+    let mut separated = query.separated(", ");
+    for pokemon_type in &filter.types {
+        separated.push_bind(*pokemon_type);
+    }
+    separated.push_unseparated(")");
+    if filter.type_match == TypeMatch::All {
+        query
+            .push(" GROUP BY pokemon_id HAVING COUNT(DISTINCT typing) = ")
+            .push_bind(filter.types.len() as u32);
+    }
+    query.push(")");
+    if let Some(matchups) = matchups {
+        matchups.push_conditions(&mut query);
+    }
+    query
+        .push(" ORDER BY pokedex_id, is_default DESC LIMIT ")
+        .push_bind(limit);
+
+    let (rows, total) =
+        queries::split_counted(query.build_query_as::<CountedRow>().fetch_all(pool).await?);
+    let pokemon = rows
+        .iter()
+        .map(|row| PokemonBody::new(row, effectiveness))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let builder = with_total_count(response::response(200), total, pokemon.len());
+    shape.list(builder, format, &pokemon)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use aws_lambda_events::query_map::QueryMap;
+
+    use crate::test_support::{body_json, db_test};
+
+    #[test]
+    fn rejects_unknown_types_and_match_modes() {
+        assert_eq!(
+            TypeFilter::parse("Water, flying,water", Some("all")),
+            Ok(TypeFilter {
+                types: vec![PokemonType::Water, PokemonType::Flying],
+                type_match: TypeMatch::All
+            })
+        );
+        assert!(TypeFilter::parse("sound", None).is_err());
+        assert!(TypeFilter::parse(",", None).is_err());
+        assert!(TypeFilter::parse("water", Some("some")).is_err());
+    }
+
+    async fn slugs(pool: &MySqlPool, types: &str, type_match: &str, limit: u32) -> Vec<String> {
+        let response = by_type(
+            pool,
+            PokemonTable::Pokemon,
+            types,
+            Some(type_match),
+            None,
+            false,
+            limit,
+            Format::Json,
+            Shape::Plain,
+            EffectivenessFormat::Raw,
+        )
+        .await
+        .unwrap();
+        let body = body_json(response);
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|pokemon| pokemon["slug"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    db_test! {
+        async fn finds_pokemon_by_type(pool: MySqlPool) {
+            assert_eq!(
+                slugs(&pool, "water,fire", "any", 100).await,
+                ["squirtle", "ho-oh"]
+            );
+            assert_eq!(slugs(&pool, "fire,flying", "all", 100).await, ["ho-oh"]);
+            assert!(slugs(&pool, "water,fire", "all", 100).await.is_empty());
+            assert_eq!(slugs(&pool, "water,fire", "any", 1).await, ["squirtle"]);
+
+            let total_count = |limit| {
+                let pool = pool.clone();
+                async move {
+                    let response = by_type(
+                        &pool,
+                        PokemonTable::Pokemon,
+                        "water,fire",
+                        None,
+                        None,
+                        false,
+                        limit,
+                        Format::Json,
+                        Shape::Plain,
+                        EffectivenessFormat::Raw,
+                    )
+                    .await
+                    .unwrap();
+                    response.headers.get("x-total-count").cloned()
+                }
+            };
+            assert_eq!(total_count(1).await.unwrap(), "2");
+            assert!(total_count(2).await.is_none());
+
+            let query = QueryMap::from(HashMap::from([(
+                "resists".to_string(),
+                "ground".to_string(),
+            )]));
+            let matchups = Matchups::parse(&query).unwrap();
+            let response = by_type(
+                &pool,
+                PokemonTable::Pokemon,
+                "water,fire",
+                None,
+                matchups.as_ref(),
+                false,
+                100,
+                Format::Json,
+                Shape::Plain,
+                EffectivenessFormat::Raw,
+            )
+            .await
+            .unwrap();
+            let body = body_json(response);
+            assert_eq!(body.as_array().unwrap().len(), 1);
+            assert_eq!(body[0]["slug"], "ho-oh");
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonTable;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::info;
+
+use crate::{
+    error::ApiError,
+    response::{self, Format},
+};
+
+const DEFAULT_BUCKETS: u8 = 10;
+const MAX_BUCKETS: u8 = 50;
+
+/// The stats a distribution can be asked for. Only these are ever written
+/// into the query, so `stat` can't inject a column or anything else.
+pub(super) const STATS: [&str; 6] = [
+    "hp",
+    "attack",
+    "defense",
+    "special_attack",
+    "special_defense",
+    "speed",
+];
+
+/// How many pokemon have a value from `min` to `max`, both inclusive.
+#[derive(Debug, PartialEq, Serialize)]
+struct Bucket {
+    min: u16,
+    max: u16,
+    count: i64,
+}
+
+fn parse_stat(stat: Option<&str>) -> Result<&'static str, ApiError> {
+    let unknown = || {
+        ApiError::InvalidQuery(format!(
+            "stat must be one of {}",
+            STATS.map(|stat| format!("`{stat}`")).join(", ")
+        ))
+    };
+    let stat = stat.ok_or_else(unknown)?;
+    STATS
+        .into_iter()
+        .find(|&known| known == stat)
+        .ok_or_else(unknown)
+}
+
+fn parse_buckets(buckets: Option<&str>) -> Result<u8, ApiError> {
+    let Some(buckets) = buckets else {
+        return Ok(DEFAULT_BUCKETS);
+    };
+    match buckets.parse() {
+        Ok(buckets @ 1..=MAX_BUCKETS) => Ok(buckets),
+        _ => Err(ApiError::InvalidQuery(format!(
+            "buckets must be a number from 1 to {MAX_BUCKETS}"
+        ))),
+    }
+}
+
+/// Splits `min..=max` into `buckets` ranges of near equal width, the same
+/// split the query makes with `(value - min) * buckets DIV width`. There are
+/// never more buckets than distinct values, so none of them is empty.
+fn bucket_bounds(min: u16, max: u16, buckets: u8) -> Vec<(u16, u16)> {
+    let width = u32::from(max - min) + 1;
+    let buckets = u32::from(buckets).min(width);
+    let start = |bucket: u32| u32::from(min) + (bucket * width).div_ceil(buckets);
+    (0..buckets)
+        .map(|bucket| (start(bucket) as u16, (start(bucket + 1) - 1) as u16))
+        .collect()
+}
+
+/// `/api/stats/distribution?stat=attack&buckets=10`, a histogram of one base
+/// stat across every pokemon, for charts.
+pub async fn distribution(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    query: &QueryMap,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let (stat, buckets, include_forms) = match (
+        parse_stat(query.first("stat")),
+        parse_buckets(query.first("buckets")),
+        super::include_forms(query),
+    ) {
+        (Ok(stat), Ok(buckets), Ok(include_forms)) => (stat, buckets, include_forms),
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+            return error.into_response()
+        }
+    };
+    info!(
+        stat,
+        buckets, include_forms, "requested a stat distribution"
+    );
+
+    let (min, max): (Option<u16>, Option<u16>) = sqlx::query_as(&format!(
+        "SELECT MIN({stat}), MAX({stat}) FROM {table} WHERE ? OR is_default"
+    ))
+    .bind(include_forms)
+    .fetch_one(pool)
+    .await?;
+    let (Some(min), Some(max)) = (min, max) else {
+        return response::response(200).format(format, &Vec::<Bucket>::new());
+    };
+
+    let bounds = bucket_bounds(min, max, buckets);
+    let width = u32::from(max - min) + 1;
+    // the stat columns are unsigned, so they're cast before subtracting
+    let counts: Vec<(i64, i64)> = sqlx::query_as(&format!(
+        "SELECT CAST((CAST({stat} AS SIGNED) - ?) * ? DIV ? AS SIGNED) AS bucket, COUNT(*) \
+        FROM {table} WHERE ? OR is_default GROUP BY bucket"
+    ))
+    .bind(min)
+    .bind(bounds.len() as u32)
+    .bind(width)
+    .bind(include_forms)
+    .fetch_all(pool)
+    .await?;
+
+    let histogram: Vec<Bucket> = bounds
+        .into_iter()
+        .enumerate()
+        .map(|(bucket, (min, max))| Bucket {
+            min,
+            max,
+            count: counts
+                .iter()
+                .find(|(index, _)| *index == bucket as i64)
+                .map_or(0, |(_, count)| *count),
+        })
+        .collect();
+
+    response::response(200).format(format, &histogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+    use std::collections::HashMap;
+
+    #[test]
+    fn only_known_stats_and_sane_bucket_counts() {
+        assert_eq!(parse_stat(Some("attack")), Ok("attack"));
+        assert!(parse_stat(Some("id; DROP TABLE pokemon")).is_err());
+        assert!(parse_stat(None).is_err());
+        assert_eq!(parse_buckets(None), Ok(DEFAULT_BUCKETS));
+        assert!(parse_buckets(Some("0")).is_err());
+        assert!(parse_buckets(Some("51")).is_err());
+    }
+
+    #[test]
+    fn buckets_cover_the_range_without_gaps() {
+        assert_eq!(bucket_bounds(44, 106, 2), [(44, 75), (76, 106)]);
+        assert_eq!(bucket_bounds(1, 10, 3), [(1, 4), (5, 7), (8, 10)]);
+        assert_eq!(bucket_bounds(5, 6, 10), [(5, 5), (6, 6)]);
+        assert_eq!(bucket_bounds(7, 7, 4), [(7, 7)]);
+    }
+
+    db_test! {
+        async fn counts_pokemon_per_bucket(pool: MySqlPool) {
+            let query = QueryMap::from(HashMap::from([
+                ("stat".to_string(), "hp".to_string()),
+                ("buckets".to_string(), "2".to_string()),
+            ]));
+            let response = distribution(&pool, PokemonTable::Pokemon, &query, Format::Json)
+                .await
+                .unwrap();
+
+            let body = body_json(response);
+            assert_eq!(
+                body,
+                serde_json::json!([
+                    { "min": 44, "max": 75, "count": 2 },
+                    { "min": 76, "max": 106, "count": 1 }
+                ])
+            );
+        }
+    }
+}
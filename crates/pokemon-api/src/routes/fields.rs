@@ -0,0 +1,188 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use serde::Serialize;
+
+use super::{distribution::STATS, matchups::Matchup, POKEMON_COLUMNS};
+use crate::response::{self, Format};
+
+/// The `/api/pokemon` query parameters that aren't matchups, each with the
+/// columns it narrows the list by. `type` and `match` narrow it by the
+/// typing table, which has no column here, and the rest change how much of
+/// the list is answered or how.
+const PARAMETERS: [(&str, &[&str]); 12] = [
+    ("slugs", &["slug"]),
+    ("pokedex_ids", &["pokedex_id"]),
+    ("strict", &[]),
+    ("name", &["name"]),
+    ("type", &[]),
+    ("match", &[]),
+    ("pokedex_from", &["pokedex_id"]),
+    ("pokedex_to", &["pokedex_id"]),
+    ("include_forms", &["is_default"]),
+    ("forms", &["is_default"]),
+    ("limit", &[]),
+    ("effectiveness", &[]),
+];
+
+/// The fields every list is ordered by, in this order. No parameter changes
+/// the order: lists come in pokedex order, the default form first.
+const LIST_ORDER: [&str; 2] = ["pokedex_id", "is_default"];
+
+#[derive(Debug, Serialize)]
+struct Metadata {
+    fields: Vec<Field>,
+    parameters: Vec<Parameter>,
+}
+
+/// A query parameter of `/api/pokemon`.
+#[derive(Debug, Serialize)]
+struct Parameter {
+    name: &'static str,
+    /// The fields it narrows the list by, if any.
+    fields: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Field {
+    name: &'static str,
+    #[serde(rename = "type")]
+    field_type: &'static str,
+    nullable: bool,
+    /// Query parameters of `/api/pokemon` that filter on this field.
+    filters: Vec<&'static str>,
+    /// Whether `/api/stats/distribution` can chart this field.
+    distribution: bool,
+    /// Whether lists are ordered by this field.
+    sortable: bool,
+}
+
+/// The JSON type a column is serialized as, and whether it can be `null`.
+fn field_type(name: &str) -> (&'static str, bool) {
+    match name {
+        "id" | "name" | "slug" | "primary_color" => ("string", false),
+        "genderless" | "legendary_or_mythical" | "is_default" | "forms_switchable" => {
+            ("boolean", false)
+        }
+        "female_rate" => ("number", true),
+        "number_pokemon_with_typing" => ("number", false),
+        name if name.ends_with("_attack_effectiveness") => ("number", false),
+        _ => ("integer", false),
+    }
+}
+
+fn columns() -> impl Iterator<Item = &'static str> {
+    POKEMON_COLUMNS.split(',').map(str::trim)
+}
+
+/// Every `/api/pokemon` parameter, the matchups taken from the same list
+/// their parser reads.
+fn parameters() -> Vec<Parameter> {
+    let matchups = Matchup::ALL.map(|matchup| Parameter {
+        name: matchup.param(),
+        fields: columns()
+            .filter(|name| name.ends_with("_attack_effectiveness"))
+            .collect(),
+    });
+    PARAMETERS
+        .iter()
+        .map(|&(name, fields)| Parameter {
+            name,
+            fields: fields.to_vec(),
+        })
+        .chain(matchups)
+        .collect()
+}
+
+/// Built from the same column list and whitelists the queries use, so it
+/// lists exactly what the other routes accept.
+fn metadata() -> Metadata {
+    let parameters = parameters();
+    let fields = columns()
+        .map(|name| {
+            let (field_type, nullable) = field_type(name);
+            Field {
+                name,
+                field_type,
+                nullable,
+                filters: parameters
+                    .iter()
+                    .filter(|parameter| parameter.fields.contains(&name))
+                    .map(|parameter| parameter.name)
+                    .collect(),
+                distribution: STATS.contains(&name),
+                sortable: LIST_ORDER.contains(&name),
+            }
+        })
+        .collect();
+    Metadata { fields, parameters }
+}
+
+/// `/api/fields`, a description of every field of a full pokemon and every
+/// `/api/pokemon` parameter, for clients that build their query controls at
+/// runtime.
+pub fn fields_metadata(format: Format) -> Result<ApiGatewayProxyResponse, Error> {
+    response::response(200).format(format, &metadata())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_every_column() {
+        let fields = metadata().fields;
+        assert_eq!(fields.len(), 41);
+
+        let hp = fields.iter().find(|field| field.name == "hp").unwrap();
+        assert_eq!(hp.field_type, "integer");
+        assert!(hp.distribution);
+
+        let pokedex_id = fields
+            .iter()
+            .find(|field| field.name == "pokedex_id")
+            .unwrap();
+        assert_eq!(
+            pokedex_id.filters,
+            ["pokedex_ids", "pokedex_from", "pokedex_to"]
+        );
+
+        let female_rate = fields
+            .iter()
+            .find(|field| field.name == "female_rate")
+            .unwrap();
+        assert_eq!(
+            (female_rate.field_type, female_rate.nullable),
+            ("number", true)
+        );
+    }
+
+    #[test]
+    fn describes_every_parameter_the_collection_parses() {
+        let parameters = parameters();
+        for source in [include_str!("mod.rs"), include_str!("pokedex_range.rs")] {
+            for key in source
+                .split("query.first(\"")
+                .skip(1)
+                .filter_map(|rest| rest.split('"').next())
+            {
+                assert!(
+                    parameters.iter().any(|parameter| parameter.name == key),
+                    "`{key}` is parsed but not described"
+                );
+            }
+        }
+
+        let fields = metadata().fields;
+        let fire = fields
+            .iter()
+            .find(|field| field.name == "fire_attack_effectiveness")
+            .unwrap();
+        assert_eq!(fire.filters, ["weak_to", "resists", "immune_to"]);
+        let sortable: Vec<_> = fields
+            .iter()
+            .filter(|field| field.sortable)
+            .map(|field| field.name)
+            .collect();
+        assert_eq!(sortable, ["pokedex_id", "is_default"]);
+    }
+}
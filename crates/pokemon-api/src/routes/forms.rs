@@ -0,0 +1,106 @@
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, PokemonTable};
+use sqlx::MySqlPool;
+use tracing::info;
+
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+};
+
+/// `/api/pokemon/id/:pokedex_id/forms`, every form of one pokedex entry.
+pub async fn forms(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokedex_id: &str,
+    query: &QueryMap,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let Ok(pokedex_id) = pokedex_id.parse::<u16>() else {
+        return ApiError::InvalidSlug("pokedex id must be a positive number").into_response();
+    };
+    let (effectiveness, shape) = match (
+        EffectivenessFormat::parse(query.first("effectiveness")),
+        Shape::parse(query.first("format"), format),
+    ) {
+        (Ok(effectiveness), Ok(shape)) => (effectiveness, shape),
+        (Err(error), _) | (_, Err(error)) => return error.into_response(),
+    };
+    info!(pokedex_id, "requested every form");
+
+    let forms = queries::pokemon_forms(pool, table, pokedex_id).await?;
+    if forms.is_empty() {
+        return ApiError::PokemonNotFound.into_response();
+    }
+    let forms = forms
+        .iter()
+        .map(|row| PokemonBody::new(row, effectiveness))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    shape.list(response::response(200), format, &forms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+    use pokemon_api::queries::POKEMON_COLUMNS;
+
+    db_test! {
+        async fn lists_forms_default_first(pool: MySqlPool) {
+            // an explicit column list, so MySQL fills in the generated and
+            // defaulted columns itself
+            let copied = POKEMON_COLUMNS
+                .split(',')
+                .map(str::trim)
+                .map(|column| match column {
+                    "id" => "'0ujsswThIGTUYm2K8FjOOfXtY1L'",
+                    "slug" => "'squirtle-delta'",
+                    "name" => "'Squirtle Delta'",
+                    "is_default" => "FALSE",
+                    column => column,
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            sqlx::query(&format!(
+                "INSERT INTO pokemon ({POKEMON_COLUMNS}) \
+                SELECT {copied} FROM pokemon WHERE slug = 'squirtle'"
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            let response = forms(
+                &pool,
+                PokemonTable::Pokemon,
+                "7",
+                &QueryMap::default(),
+                Format::Json,
+            )
+            .await
+            .unwrap();
+            let body = body_json(response);
+            let slugs: Vec<_> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pokemon| pokemon["slug"].as_str().unwrap())
+                .collect();
+            assert_eq!(slugs, ["squirtle", "squirtle-delta"]);
+
+            let missing = forms(
+                &pool,
+                PokemonTable::Pokemon,
+                "9999",
+                &QueryMap::default(),
+                Format::Json,
+            )
+            .await
+            .unwrap();
+            assert_eq!(missing.status_code, 404);
+        }
+    }
+}
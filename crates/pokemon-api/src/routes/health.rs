@@ -0,0 +1,65 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::MySqlPool;
+
+use crate::response;
+
+/// How busy the connection pool is, read without waiting on it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PoolStats {
+    /// Connections open, idle or in use.
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+impl PoolStats {
+    pub fn of(pool: &MySqlPool) -> Self {
+        Self {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            max: pool.options().get_max_connections(),
+        }
+    }
+}
+
+/// `/health`, which answers even in maintenance mode so deploy tooling can
+/// tell a deliberate outage from a broken one. It doesn't touch the database,
+/// but reports the pool's gauges for monitoring; load balancers should only
+/// look at `status`.
+pub fn health(
+    maintenance: bool,
+    pool: Option<PoolStats>,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let status = if maintenance { "maintenance" } else { "ok" };
+    response::response(200).json(&json!({ "status": status, "pool": pool }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::body_json;
+
+    #[test]
+    fn reports_maintenance() {
+        assert_eq!(
+            body_json(health(true, None).unwrap()),
+            json!({ "status": "maintenance", "pool": null })
+        );
+    }
+
+    #[test]
+    fn reports_pool_gauges() {
+        let pool = PoolStats {
+            size: 3,
+            idle: 2,
+            max: 5,
+        };
+        assert_eq!(
+            body_json(health(false, Some(pool)).unwrap()),
+            json!({ "status": "ok", "pool": { "size": 3, "idle": 2, "max": 5 } })
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, CountedRow, TOTAL_COUNT};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::info;
+use upload_pokemon_data::PokemonType;
+
+use super::{with_total_count, PokemonTable, POKEMON_COLUMNS};
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+};
+
+/// How a pokemon takes attacks of a type, read straight off its
+/// `*_attack_effectiveness` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Matchup {
+    WeakTo,
+    Resists,
+    ImmuneTo,
+}
+
+impl Matchup {
+    pub(super) const ALL: [Matchup; 3] = [Matchup::WeakTo, Matchup::Resists, Matchup::ImmuneTo];
+
+    pub(super) fn param(self) -> &'static str {
+        match self {
+            Matchup::WeakTo => "weak_to",
+            Matchup::Resists => "resists",
+            Matchup::ImmuneTo => "immune_to",
+        }
+    }
+
+    /// Compared against the attacking type's column. Immunities count as
+    /// resistances too.
+    fn comparison(self) -> &'static str {
+        match self {
+            Matchup::WeakTo => " >= 2.0",
+            Matchup::Resists => " <= 0.5",
+            Matchup::ImmuneTo => " = 0.0",
+        }
+    }
+}
+
+/// `?weak_to=`, `?resists=` and `?immune_to=`, each a comma separated list of
+/// types. A pokemon has to meet every one of them.
+#[derive(Debug, PartialEq)]
+pub struct Matchups(Vec<(Matchup, PokemonType)>);
+
+impl Matchups {
+    /// `None` when the query asks for no matchups at all.
+    pub fn parse(query: &QueryMap) -> Result<Option<Matchups>, ApiError> {
+        let mut matchups = Vec::new();
+        for matchup in Matchup::ALL {
+            let Some(types) = query.first(matchup.param()) else {
+                continue;
+            };
+            let before = matchups.len();
+            for name in types
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                let pokemon_type: PokemonType = name
+                    .parse()
+                    .map_err(|error| ApiError::InvalidQuery(error.to_string()))?;
+                if !matchups.contains(&(matchup, pokemon_type)) {
+                    matchups.push((matchup, pokemon_type));
+                }
+            }
+            if matchups.len() == before {
+                return Err(ApiError::InvalidQuery(format!(
+                    "{} must name at least one type",
+                    matchup.param()
+                )));
+            }
+        }
+        Ok((!matchups.is_empty()).then_some(Matchups(matchups)))
+    }
+
+    /// Adds a condition per matchup to a query already inside its `WHERE`.
+    pub fn push_conditions(&self, query: &mut QueryBuilder<MySql>) {
+        for (matchup, pokemon_type) in &self.0 {
+            query
+                .push(" AND ")
+                .push(pokemon_type.effectiveness_column())
+                .push(matchup.comparison());
+        }
+    }
+}
+
+/// `/api/pokemon?weak_to=water&resists=fire&immune_to=ground`, the first
+/// `limit` by pokedex id.
+pub async fn by_matchups(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    matchups: &Matchups,
+    include_forms: bool,
+    limit: u32,
+    format: Format,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    info!(?matchups, include_forms, "requested pokemon by matchup");
+
+    let mut query = QueryBuilder::<MySql>::new(format!(
+        "SELECT {POKEMON_COLUMNS}, {TOTAL_COUNT} FROM {table} WHERE ("
+    ));
+    query.push_bind(include_forms).push(" OR is_default)");
+    matchups.push_conditions(&mut query);
+    query
+        .push(" ORDER BY pokedex_id, is_default DESC LIMIT ")
+        .push_bind(limit);
+
+    let (rows, total) =
+        queries::split_counted(query.build_query_as::<CountedRow>().fetch_all(pool).await?);
+    let pokemon = rows
+        .iter()
+        .map(|row| PokemonBody::new(row, effectiveness))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let builder = with_total_count(response::response(200), total, pokemon.len());
+    shape.list(builder, format, &pokemon)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+
+    fn query(params: &[(&str, &str)]) -> QueryMap {
+        QueryMap::from(
+            params
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn parses_each_matchup_and_rejects_unknown_types() {
+        assert_eq!(Matchups::parse(&query(&[("type", "water")])), Ok(None));
+        assert_eq!(
+            Matchups::parse(&query(&[
+                ("weak_to", "Water, water"),
+                ("immune_to", "ground")
+            ])),
+            Ok(Some(Matchups(vec![
+                (Matchup::WeakTo, PokemonType::Water),
+                (Matchup::ImmuneTo, PokemonType::Ground),
+            ])))
+        );
+        assert!(Matchups::parse(&query(&[("resists", "sound")])).is_err());
+        assert!(Matchups::parse(&query(&[("resists", " , ")])).is_err());
+    }
+
+    async fn slugs(pool: &MySqlPool, params: &[(&str, &str)]) -> Vec<String> {
+        let matchups = Matchups::parse(&query(params)).unwrap().unwrap();
+        let response = by_matchups(
+            pool,
+            PokemonTable::Pokemon,
+            &matchups,
+            false,
+            100,
+            Format::Json,
+            Shape::Plain,
+            EffectivenessFormat::Raw,
+        )
+        .await
+        .unwrap();
+        let body = body_json(response);
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|pokemon| pokemon["slug"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    db_test! {
+        async fn compares_the_attacking_types_column(pool: MySqlPool) {
+            assert_eq!(slugs(&pool, &[("weak_to", "water")]).await, ["ho-oh"]);
+            assert_eq!(
+                slugs(&pool, &[("weak_to", "fire,flying")]).await,
+                ["bulbasaur"]
+            );
+            assert_eq!(
+                slugs(&pool, &[("resists", "fire")]).await,
+                ["squirtle", "ho-oh"]
+            );
+            // immune counts as resisting
+            assert_eq!(slugs(&pool, &[("resists", "ground")]).await, ["ho-oh"]);
+            assert_eq!(slugs(&pool, &[("immune_to", "ground")]).await, ["ho-oh"]);
+            assert_eq!(
+                slugs(&pool, &[("resists", "fire"), ("weak_to", "electric")]).await,
+                ["squirtle", "ho-oh"]
+            );
+            assert!(slugs(&pool, &[("immune_to", "water")]).await.is_empty());
+        }
+    }
+}
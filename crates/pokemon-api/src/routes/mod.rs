@@ -0,0 +1,368 @@
+mod admin;
+mod by_name;
+mod by_type;
+mod distribution;
+mod fields;
+mod forms;
+mod health;
+mod matchups;
+mod multi_pokedex_id;
+mod multi_slug;
+mod percentiles;
+mod pokedex_range;
+mod pokemon_id;
+mod random_team;
+mod similar;
+mod type_stats;
+mod update;
+
+pub use admin::{explain, ADMIN_TOKEN};
+pub use distribution::distribution;
+pub use fields::fields_metadata;
+pub use forms::forms;
+pub use health::{health, PoolStats};
+pub use percentiles::percentiles;
+pub use pokemon_id::pokemon_id;
+pub use random_team::random_team;
+pub use similar::similar;
+pub use type_stats::type_stats;
+pub use update::update_pokemon;
+
+use std::{collections::HashSet, env, hash::Hash};
+
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use lambda_runtime::Error;
+use once_cell::sync::Lazy;
+use pokemon_api::queries::{PokemonTable, POKEMON_COLUMNS};
+use sqlx::MySqlPool;
+
+use self::matchups::Matchups;
+use crate::{
+    effectiveness::EffectivenessFormat,
+    error::ApiError,
+    jsonapi::Shape,
+    response::{Format, ResponseBuilder},
+};
+
+/// Whether lists include alternate forms (megas, regional variants) when the
+/// client doesn't say, from `INCLUDE_FORMS_BY_DEFAULT` (`1` or `true`). Most
+/// clients only want the canonical pokedex, so it's off unless set.
+static INCLUDE_FORMS_BY_DEFAULT: Lazy<bool> = Lazy::new(|| {
+    env::var("INCLUDE_FORMS_BY_DEFAULT").is_ok_and(|value| matches!(value.as_str(), "1" | "true"))
+});
+
+/// How many pokemon a list answers with when the client doesn't pass
+/// `?limit=`, from `LIST_DEFAULT_LIMIT`. Defaults to 100.
+static LIST_DEFAULT_LIMIT: Lazy<u32> = Lazy::new(|| limit_from_env("LIST_DEFAULT_LIMIT", 100));
+
+/// The most pokemon any list answers with, from `LIST_MAX_LIMIT`. Defaults
+/// to 500. A larger `?limit=` or `?n=` is clamped to it rather than rejected.
+/// It also caps how many slugs or pokedex ids one lookup may name, and how
+/// many pokedex ids a range may span; forms can add rows past that.
+static LIST_MAX_LIMIT: Lazy<u32> = Lazy::new(|| limit_from_env("LIST_MAX_LIMIT", 500));
+
+fn limit_from_env(name: &str, default: u32) -> u32 {
+    env::var(name)
+        .ok()
+        .and_then(|limit| limit.parse().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(default)
+}
+
+/// How many pokemon a list should answer with at most, from `?limit=`. Every
+/// list shares the same default and cap.
+fn limit(query: &QueryMap) -> Result<u32, ApiError> {
+    parse_limit(
+        "limit",
+        query.first("limit"),
+        *LIST_DEFAULT_LIMIT,
+        *LIST_MAX_LIMIT,
+    )
+}
+
+/// A count of pokemon from the query parameter `param`, clamped to `max`.
+fn parse_limit(param: &str, limit: Option<&str>, default: u32, max: u32) -> Result<u32, ApiError> {
+    let Some(limit) = limit else {
+        return Ok(default.min(max));
+    };
+    match limit.parse::<u32>() {
+        Ok(limit) if limit > 0 => Ok(limit.min(max)),
+        _ => Err(ApiError::InvalidQuery(format!(
+            "{param} must be a positive number, got `{limit}`"
+        ))),
+    }
+}
+
+/// Lists cut short by their limit say how many pokemon matched in
+/// `X-Total-Count`, so clients can tell they didn't get all of them.
+fn with_total_count(builder: ResponseBuilder, total: u64, returned: usize) -> ResponseBuilder {
+    if total <= returned as u64 {
+        return builder;
+    }
+    builder.header(
+        HeaderName::from_static("x-total-count"),
+        HeaderValue::from(total),
+    )
+}
+
+/// Whether a list, search or filter should include alternate forms, from
+/// `?include_forms=true|false`. The older `forms=all|default`, which pokedex
+/// ranges and name lookups took first, is still understood.
+fn include_forms(query: &QueryMap) -> Result<bool, ApiError> {
+    parse_include_forms(
+        query.first("include_forms"),
+        query.first("forms"),
+        *INCLUDE_FORMS_BY_DEFAULT,
+    )
+}
+
+fn parse_include_forms(
+    include_forms: Option<&str>,
+    forms: Option<&str>,
+    default: bool,
+) -> Result<bool, ApiError> {
+    match (include_forms, forms) {
+        (Some("true"), _) => Ok(true),
+        (Some("false"), _) => Ok(false),
+        (Some(_), _) => Err(ApiError::InvalidQuery(
+            "include_forms must be one of `true` or `false`".to_string(),
+        )),
+        (None, Some("all")) => Ok(true),
+        (None, Some("default")) => Ok(false),
+        (None, Some(_)) => Err(ApiError::InvalidQuery(
+            "forms must be one of `default` or `all`".to_string(),
+        )),
+        (None, None) => Ok(default),
+    }
+}
+
+/// `items` with repeats dropped, each kept where it first appeared, so asking
+/// for `pikachu,pikachu` answers with one pikachu.
+fn dedup_in_order<T: Copy + Eq + Hash>(items: Vec<T>) -> Vec<T> {
+    let mut seen = HashSet::with_capacity(items.len());
+    items
+        .into_iter()
+        .filter(|item| seen.insert(*item))
+        .collect()
+}
+
+/// `/api/pokemon`, which answers different lookups depending on the query.
+pub async fn pokemon_collection(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    path: &str,
+    query: &QueryMap,
+    headers: &HeaderMap,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let effectiveness = match EffectivenessFormat::parse(query.first("effectiveness")) {
+        Ok(effectiveness) => effectiveness,
+        Err(error) => return error.into_response(),
+    };
+    let format = Format::from_accept(headers);
+    let shape = match Shape::parse(query.first("format"), format) {
+        Ok(shape) => shape,
+        Err(error) => return error.into_response(),
+    };
+    let include_forms = match include_forms(query) {
+        Ok(include_forms) => include_forms,
+        Err(error) => return error.into_response(),
+    };
+    let matchups = match Matchups::parse(query) {
+        Ok(matchups) => matchups,
+        Err(error) => return error.into_response(),
+    };
+    let limit = match limit(query) {
+        Ok(limit) => limit,
+        Err(error) => return error.into_response(),
+    };
+    // asking for slugs or pokedex ids already says which forms are wanted
+    if let Some(slugs) = query.first("slugs") {
+        return multi_slug::multi_slug(pool, table, slugs, format, shape, effectiveness).await;
+    }
+    if let Some(pokedex_ids) = query.first("pokedex_ids") {
+        let strict = query.first("strict");
+        return multi_pokedex_id::multi_pokedex_id(
+            pool,
+            table,
+            pokedex_ids,
+            strict,
+            format,
+            shape,
+            effectiveness,
+        )
+        .await;
+    }
+    if let Some(name) = query.first("name") {
+        return by_name::by_name(
+            pool,
+            table,
+            name,
+            include_forms,
+            limit,
+            format,
+            shape,
+            effectiveness,
+        )
+        .await;
+    }
+    if let Some(types) = query.first("type") {
+        let type_match = query.first("match");
+        return by_type::by_type(
+            pool,
+            table,
+            types,
+            type_match,
+            matchups.as_ref(),
+            include_forms,
+            limit,
+            format,
+            shape,
+            effectiveness,
+        )
+        .await;
+    }
+    if let Some(matchups) = matchups {
+        return matchups::by_matchups(
+            pool,
+            table,
+            &matchups,
+            include_forms,
+            limit,
+            format,
+            shape,
+            effectiveness,
+        )
+        .await;
+    }
+    pokedex_range::pokedex_range(
+        pool,
+        table,
+        path,
+        query,
+        headers,
+        include_forms,
+        shape,
+        effectiveness,
+    )
+    .await
+}
+
+/// The six base stats of a single pokemon, and which pokedex entry it is.
+#[derive(Debug)]
+struct BaseStats {
+    pokedex_id: u16,
+    hp: u16,
+    attack: u16,
+    defense: u16,
+    special_attack: u16,
+    special_defense: u16,
+    speed: u16,
+}
+
+async fn fetch_base_stats(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+) -> Result<Option<BaseStats>, sqlx::Error> {
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_as!(
+                BaseStats,
+                r#"
+SELECT
+    pokedex_id,
+    hp,
+    attack,
+    defense,
+    special_attack,
+    special_defense,
+    speed
+FROM
+    pokemon
+WHERE
+    slug = ?
+"#,
+                slug
+            )
+            .fetch_optional(pool)
+            .await
+        }
+        PokemonTable::Staging => {
+            sqlx::query_as!(
+                BaseStats,
+                r#"
+SELECT
+    pokedex_id,
+    hp,
+    attack,
+    defense,
+    special_attack,
+    special_defense,
+    speed
+FROM
+    pokemon_staging
+WHERE
+    slug = ?
+"#,
+                slug
+            )
+            .fetch_optional(pool)
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_forms_falls_back_to_forms_then_the_default() {
+        assert_eq!(
+            parse_include_forms(Some("true"), Some("default"), false),
+            Ok(true)
+        );
+        assert_eq!(parse_include_forms(Some("false"), None, true), Ok(false));
+        assert_eq!(parse_include_forms(None, Some("all"), false), Ok(true));
+        assert_eq!(parse_include_forms(None, None, true), Ok(true));
+        assert_eq!(parse_include_forms(None, None, false), Ok(false));
+        assert!(parse_include_forms(Some("yes"), None, false).is_err());
+        assert!(parse_include_forms(None, Some("mega"), false).is_err());
+    }
+
+    #[test]
+    fn limit_is_clamped_to_the_max() {
+        assert_eq!(parse_limit("limit", None, 100, 500), Ok(100));
+        assert_eq!(parse_limit("limit", None, 100, 50), Ok(50));
+        assert_eq!(parse_limit("limit", Some("20"), 100, 500), Ok(20));
+        assert_eq!(parse_limit("limit", Some("1000"), 100, 500), Ok(500));
+        assert!(parse_limit("limit", Some("0"), 100, 500).is_err());
+        assert!(parse_limit("limit", Some("-1"), 100, 500).is_err());
+        assert!(parse_limit("limit", Some("all"), 100, 500).is_err());
+    }
+
+    #[test]
+    fn only_truncated_lists_carry_a_total_count() {
+        let total_count = |total, returned| {
+            let response =
+                with_total_count(crate::response::response(200), total, returned).empty();
+            response
+                .headers
+                .get("x-total-count")
+                .map(|total| total.to_str().unwrap().to_string())
+        };
+        assert_eq!(total_count(3, 3), None);
+        assert_eq!(total_count(0, 0), None);
+        assert_eq!(total_count(151, 100), Some("151".to_string()));
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each() {
+        assert_eq!(
+            dedup_in_order(vec!["pikachu", "raichu", "pikachu", "pichu", "raichu"]),
+            ["pikachu", "raichu", "pichu"]
+        );
+        assert_eq!(dedup_in_order(vec![7, 7, 1]), [7, 1]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, PokemonTable};
+use serde_json::json;
+use sqlx::MySqlPool;
+use tracing::info;
+use upload_pokemon_data::PokemonTableRow;
+
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::{self, Document, Resource, Shape},
+    response::{self, Format},
+};
+
+use super::{dedup_in_order, LIST_MAX_LIMIT};
+
+/// At most `max` pokedex ids, since each may answer with a pokemon.
+fn parse_pokedex_ids(pokedex_ids: &str, max: usize) -> Result<Vec<u16>, ApiError> {
+    let pokedex_ids = pokedex_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|pokedex_id| !pokedex_id.is_empty())
+        .map(|pokedex_id| {
+            pokedex_id.parse().map_err(|_| {
+                ApiError::InvalidQuery(format!(
+                    "pokedex_ids must be positive numbers, got `{pokedex_id}`"
+                ))
+            })
+        })
+        .collect::<Result<Vec<u16>, _>>()?;
+    let pokedex_ids = dedup_in_order(pokedex_ids);
+    if pokedex_ids.is_empty() {
+        return Err(ApiError::InvalidQuery(
+            "pokedex_ids must name at least one pokemon".to_string(),
+        ));
+    }
+    if pokedex_ids.len() > max {
+        return Err(ApiError::InvalidQuery(format!(
+            "pokedex_ids may name at most {max} pokemon"
+        )));
+    }
+    Ok(pokedex_ids)
+}
+
+fn parse_strict(strict: Option<&str>) -> Result<bool, ApiError> {
+    match strict {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(_) => Err(ApiError::InvalidQuery(
+            "strict must be `true` or `false`".to_string(),
+        )),
+    }
+}
+
+/// `/api/pokemon?pokedex_ids=1,4,7`, the default forms in request order.
+/// Ids with no pokemon are left out, or with `strict=true` answered with
+/// `null` so the list lines up with the request. Repeated ids collapse into
+/// the first, `strict` or not.
+pub async fn multi_pokedex_id(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokedex_ids: &str,
+    strict: Option<&str>,
+    format: Format,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let (pokedex_ids, strict) = match (
+        parse_pokedex_ids(pokedex_ids, *LIST_MAX_LIMIT as usize),
+        parse_strict(strict),
+    ) {
+        (Ok(pokedex_ids), Ok(strict)) => (pokedex_ids, strict),
+        (Err(error), _) | (_, Err(error)) => return error.into_response(),
+    };
+    info!(?pokedex_ids, strict, "requested pokemon by pokedex id");
+
+    let rows: HashMap<u16, PokemonTableRow> =
+        queries::pokemon_by_pokedex_ids(pool, table, &pokedex_ids)
+            .await?
+            .into_iter()
+            .map(|row| (row.pokedex_id, row))
+            .collect();
+
+    let pokemon = pokedex_ids
+        .iter()
+        .map(|pokedex_id| rows.get(pokedex_id))
+        .filter(|row| strict || row.is_some())
+        .map(|row| {
+            row.map(|row| PokemonBody::new(row, effectiveness))
+                .transpose()
+        })
+        .collect::<Result<Vec<Option<PokemonBody>>, _>>()?;
+
+    let builder = response::response(200);
+    match shape {
+        Shape::Plain => builder.format(format, &pokemon),
+        Shape::JsonApi => {
+            // resource lists can't hold nulls, so missing ids are listed under
+            // `meta` the way a multi-slug lookup lists missing slugs
+            let missing: Vec<_> = pokedex_ids
+                .iter()
+                .filter(|pokedex_id| !rows.contains_key(*pokedex_id))
+                .collect();
+            let found: Vec<_> = pokemon.iter().flatten().map(Resource::of).collect();
+            jsonapi::respond(
+                builder,
+                &Document::new(found).meta(json!({ "missing": missing })),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+
+    #[test]
+    fn rejects_bad_and_oversized_id_lists() {
+        assert!(parse_pokedex_ids(" , ", 50).is_err());
+        assert!(parse_pokedex_ids("1,pikachu", 50).is_err());
+        let too_many: Vec<String> = (1..=51).map(|n| n.to_string()).collect();
+        assert!(parse_pokedex_ids(&too_many.join(","), 50).is_err());
+        assert_eq!(parse_pokedex_ids("1, 4,7", 50).unwrap(), [1, 4, 7]);
+        assert_eq!(parse_pokedex_ids("7,1,07,1", 50).unwrap(), [7, 1]);
+        assert!(parse_strict(Some("yes")).is_err());
+    }
+
+    db_test! {
+        async fn keeps_request_order_and_handles_missing_ids(pool: MySqlPool) {
+            let slugs = |strict| {
+                let pool = pool.clone();
+                async move {
+                    let response = multi_pokedex_id(
+                        &pool,
+                        PokemonTable::Pokemon,
+                        "7,9999,1",
+                        strict,
+                        Format::Json,
+                        Shape::Plain,
+                        EffectivenessFormat::Raw,
+                    )
+                    .await
+                    .unwrap();
+                    let body = body_json(response);
+                    body.as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|pokemon| pokemon["slug"].as_str().map(str::to_string))
+                        .collect::<Vec<_>>()
+                }
+            };
+
+            assert_eq!(
+                slugs(None).await,
+                [Some("squirtle".to_string()), Some("bulbasaur".to_string())]
+            );
+            assert_eq!(
+                slugs(Some("true")).await,
+                [
+                    Some("squirtle".to_string()),
+                    None,
+                    Some("bulbasaur".to_string())
+                ]
+            );
+
+            let response = multi_pokedex_id(
+                &pool,
+                PokemonTable::Pokemon,
+                "7,9999,1",
+                Some("true"),
+                Format::Json,
+                Shape::JsonApi,
+                EffectivenessFormat::Raw,
+            )
+            .await
+            .unwrap();
+            let body = body_json(response);
+            let slugs: Vec<_> = body["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pokemon| pokemon["attributes"]["slug"].as_str().unwrap())
+                .collect();
+            assert_eq!(slugs, ["squirtle", "bulbasaur"]);
+            assert_eq!(body["meta"], json!({ "missing": [9999] }));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, PokemonTable};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::MySqlPool;
+use tracing::info;
+use upload_pokemon_data::PokemonTableRow;
+
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::{self, Document, Shape},
+    response::{self, Format},
+};
+
+use super::{dedup_in_order, LIST_MAX_LIMIT};
+
+/// Slugs that exist come back in request order under `found`, the rest are
+/// listed under `missing`, so one typo doesn't fail the whole lookup. A slug
+/// asked for twice is only answered once, where it first appeared.
+#[derive(Debug, Serialize)]
+struct MultiSlugLookup<'a> {
+    found: Vec<PokemonBody<'a>>,
+    missing: Vec<String>,
+}
+
+/// At most `max` slugs, since each may answer with a pokemon.
+fn parse_slugs(slugs: &str, max: usize) -> Result<Vec<&str>, ApiError> {
+    let slugs = dedup_in_order(
+        slugs
+            .split(',')
+            .map(str::trim)
+            .filter(|slug| !slug.is_empty())
+            .collect(),
+    );
+    if slugs.is_empty() {
+        return Err(ApiError::InvalidQuery(
+            "slugs must name at least one pokemon".to_string(),
+        ));
+    }
+    if slugs.len() > max {
+        return Err(ApiError::InvalidQuery(format!(
+            "slugs may name at most {max} pokemon"
+        )));
+    }
+    Ok(slugs)
+}
+
+pub async fn multi_slug(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slugs: &str,
+    format: Format,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let slugs = match parse_slugs(slugs, *LIST_MAX_LIMIT as usize) {
+        Ok(slugs) => slugs,
+        Err(error) => return error.into_response(),
+    };
+    info!(?slugs, "requested multiple pokemon");
+
+    let mut rows: HashMap<String, PokemonTableRow> = queries::pokemon_by_slugs(pool, table, &slugs)
+        .await?
+        .into_iter()
+        .map(|row| (row.slug.clone(), row))
+        .collect();
+
+    let mut found = Vec::with_capacity(rows.len());
+    let mut missing = Vec::new();
+    for slug in slugs {
+        match rows.remove(slug) {
+            Some(row) => found.push(row),
+            None => missing.push(slug.to_string()),
+        }
+    }
+    let lookup = MultiSlugLookup {
+        found: found
+            .iter()
+            .map(|row| PokemonBody::new(row, effectiveness))
+            .collect::<Result<_, _>>()?,
+        missing,
+    };
+
+    let builder = response::response(200);
+    match shape {
+        Shape::Plain => builder.format(format, &lookup),
+        Shape::JsonApi => jsonapi::respond(
+            builder,
+            &Document::new(jsonapi::resources(&lookup.found))
+                .meta(json!({ "missing": lookup.missing })),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+
+    #[test]
+    fn rejects_empty_and_oversized_slug_lists() {
+        assert!(parse_slugs(" , ", 50).is_err());
+        let too_many: Vec<String> = (0..=50).map(|n| format!("fakemon-{n}")).collect();
+        assert!(parse_slugs(&too_many.join(","), 50).is_err());
+        assert_eq!(
+            parse_slugs("pikachu, raichu", 50).unwrap(),
+            ["pikachu", "raichu"]
+        );
+        assert_eq!(
+            parse_slugs("pikachu,raichu, pikachu", 50).unwrap(),
+            ["pikachu", "raichu"]
+        );
+        assert!(parse_slugs(&vec!["pikachu"; 51].join(","), 50).is_ok());
+    }
+
+    db_test! {
+        async fn reports_found_and_missing_slugs(pool: MySqlPool) {
+            let response = multi_slug(
+                &pool,
+                PokemonTable::Pokemon,
+                "squirtle,missingno,bulbasaur",
+                Format::Json,
+                Shape::Plain,
+                EffectivenessFormat::Raw,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(response.status_code, 200);
+            let body = body_json(response);
+            assert_eq!(body["found"][0]["slug"], "squirtle");
+            assert_eq!(body["found"][1]["slug"], "bulbasaur");
+            assert_eq!(body["missing"], serde_json::json!(["missingno"]));
+
+            let response = multi_slug(
+                &pool,
+                PokemonTable::Pokemon,
+                "squirtle",
+                Format::Json,
+                Shape::Plain,
+                EffectivenessFormat::Map,
+            )
+            .await
+            .unwrap();
+
+            let body = body_json(response);
+            let squirtle = &body["found"][0];
+            assert_eq!(squirtle["effectiveness"]["electric"], 2.0);
+            assert_eq!(squirtle["effectiveness"]["fire"], 0.5);
+            assert!(squirtle.get("electric_attack_effectiveness").is_none());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonTable;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::info;
+
+use super::fetch_base_stats;
+use crate::{
+    pokemon_not_found,
+    response::{self, Format},
+};
+
+/// Percentage of all pokemon with a strictly lower value for each stat.
+#[derive(Debug, Serialize)]
+struct StatPercentiles {
+    hp: f64,
+    attack: f64,
+    defense: f64,
+    special_attack: f64,
+    special_defense: f64,
+    speed: f64,
+}
+
+pub async fn percentiles(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    info!(slug, "requested stat percentiles");
+    let Some(stats) = fetch_base_stats(pool, table, slug).await? else {
+        return pokemon_not_found(pool, table, slug).await;
+    };
+
+    let percentiles = match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_as!(
+                StatPercentiles,
+                r#"
+SELECT
+    CAST(100 * SUM(hp < ?) / COUNT(*) AS DOUBLE) as "hp!: f64",
+    CAST(100 * SUM(attack < ?) / COUNT(*) AS DOUBLE) as "attack!: f64",
+    CAST(100 * SUM(defense < ?) / COUNT(*) AS DOUBLE) as "defense!: f64",
+    CAST(100 * SUM(special_attack < ?) / COUNT(*) AS DOUBLE) as "special_attack!: f64",
+    CAST(100 * SUM(special_defense < ?) / COUNT(*) AS DOUBLE) as "special_defense!: f64",
+    CAST(100 * SUM(speed < ?) / COUNT(*) AS DOUBLE) as "speed!: f64"
+FROM
+    pokemon
+"#,
+                stats.hp,
+                stats.attack,
+                stats.defense,
+                stats.special_attack,
+                stats.special_defense,
+                stats.speed
+            )
+            .fetch_one(pool)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query_as!(
+                StatPercentiles,
+                r#"
+SELECT
+    CAST(100 * SUM(hp < ?) / COUNT(*) AS DOUBLE) as "hp!: f64",
+    CAST(100 * SUM(attack < ?) / COUNT(*) AS DOUBLE) as "attack!: f64",
+    CAST(100 * SUM(defense < ?) / COUNT(*) AS DOUBLE) as "defense!: f64",
+    CAST(100 * SUM(special_attack < ?) / COUNT(*) AS DOUBLE) as "special_attack!: f64",
+    CAST(100 * SUM(special_defense < ?) / COUNT(*) AS DOUBLE) as "special_defense!: f64",
+    CAST(100 * SUM(speed < ?) / COUNT(*) AS DOUBLE) as "speed!: f64"
+FROM
+    pokemon_staging
+"#,
+                stats.hp,
+                stats.attack,
+                stats.defense,
+                stats.special_attack,
+                stats.special_defense,
+                stats.speed
+            )
+            .fetch_one(pool)
+            .await?
+        }
+    };
+
+    response::response(200).format(format, &percentiles)
+}
@@ -0,0 +1,365 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use http::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, LAST_MODIFIED, LINK};
+use lambda_runtime::Error;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use tracing::{info, warn};
+
+use pokemon_api::queries::{self, PokemonTable};
+use sqlx::MySqlPool;
+
+use super::LIST_MAX_LIMIT;
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+    tombstones::is_missing_table_error,
+};
+
+#[derive(Debug, PartialEq)]
+struct PokedexRange {
+    from: u16,
+    to: u16,
+    include_forms: bool,
+}
+
+impl PokedexRange {
+    fn parse(
+        from: Option<&str>,
+        to: Option<&str>,
+        include_forms: bool,
+        max_span: u32,
+    ) -> Result<PokedexRange, String> {
+        let from: u16 = from
+            .ok_or("pokedex_from is required")?
+            .parse()
+            .map_err(|_| "pokedex_from must be a positive number")?;
+        let to: u16 = to
+            .ok_or("pokedex_to is required")?
+            .parse()
+            .map_err(|_| "pokedex_to must be a positive number")?;
+        if from > to {
+            return Err("pokedex_from must be less than or equal to pokedex_to".to_string());
+        }
+        // so nobody pulls the whole table in one shot
+        if u32::from(to - from) >= max_span {
+            return Err(format!(
+                "pokedex range is too large, request at most {max_span} ids at once"
+            ));
+        }
+
+        Ok(PokedexRange {
+            from,
+            to,
+            include_forms,
+        })
+    }
+}
+
+impl PokedexRange {
+    fn span(&self) -> u16 {
+        self.to - self.from + 1
+    }
+
+    /// The same-sized ranges to link to as `first`, `prev` and `next`. There
+    /// is no `prev` from the first page, and no `next` once a page comes back
+    /// empty.
+    fn links(&self, page_is_empty: bool) -> Vec<(&'static str, u16, u16)> {
+        let span = self.span();
+        let mut links = vec![("first", 1, span)];
+        if self.from > 1 {
+            let prev_from = self.from.saturating_sub(span).max(1);
+            links.push(("prev", prev_from, self.from - 1));
+        }
+        if !page_is_empty {
+            if let Some(next_from) = self.to.checked_add(1) {
+                links.push(("next", next_from, next_from.saturating_add(span - 1)));
+            }
+        }
+        links
+    }
+}
+
+/// An RFC 5988 `Link` header for paging through the pokedex. Every other
+/// query parameter is kept as the client sent it.
+fn link_header(path: &str, query: &QueryMap, links: &[(&str, u16, u16)]) -> String {
+    let mut params: Vec<(&str, &str)> = query
+        .iter()
+        .filter(|(key, _)| !matches!(*key, "pokedex_from" | "pokedex_to"))
+        .collect();
+    params.sort_unstable();
+    let params: String = params
+        .into_iter()
+        .map(|(key, value)| {
+            format!(
+                "&{}={}",
+                utf8_percent_encode(key, NON_ALPHANUMERIC),
+                utf8_percent_encode(value, NON_ALPHANUMERIC)
+            )
+        })
+        .collect();
+
+    links
+        .iter()
+        .map(|(rel, from, to)| {
+            format!(r#"<{path}?pokedex_from={from}&pokedex_to={to}{params}>; rel="{rel}""#)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// When any row in the table was last written or deleted, or `None` for an
+/// empty table with no tombstones. A database that predates the `tombstones`
+/// table only counts writes.
+async fn table_last_modified(
+    pool: &MySqlPool,
+    table: PokemonTable,
+) -> Result<Option<SystemTime>, sqlx::Error> {
+    let result = match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    CAST(UNIX_TIMESTAMP(MAX(changed_at)) AS SIGNED)
+FROM (
+    SELECT MAX(updated_at) AS changed_at FROM pokemon
+    UNION ALL
+    SELECT MAX(deleted_at) FROM tombstones
+) AS changes
+"#
+            )
+            .fetch_one(pool)
+            .await
+        }
+        PokemonTable::Staging => {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    CAST(UNIX_TIMESTAMP(MAX(changed_at)) AS SIGNED)
+FROM (
+    SELECT MAX(updated_at) AS changed_at FROM pokemon_staging
+    UNION ALL
+    SELECT MAX(deleted_at) FROM tombstones
+) AS changes
+"#
+            )
+            .fetch_one(pool)
+            .await
+        }
+    };
+    let last_modified = match result {
+        Err(error) if is_missing_table_error(&error) => {
+            warn!("tombstones table does not exist; run create-tables.sql");
+            table_last_updated(pool, table).await?
+        }
+        result => result?,
+    };
+
+    Ok(last_modified
+        .and_then(|seconds| u64::try_from(seconds).ok())
+        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)))
+}
+
+/// Unix time of the latest `updated_at` in the table, ignoring deletions.
+async fn table_last_updated(
+    pool: &MySqlPool,
+    table: PokemonTable,
+) -> Result<Option<i64>, sqlx::Error> {
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    CAST(UNIX_TIMESTAMP(MAX(updated_at)) AS SIGNED)
+FROM
+    pokemon
+"#
+            )
+            .fetch_one(pool)
+            .await
+        }
+        PokemonTable::Staging => {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    CAST(UNIX_TIMESTAMP(MAX(updated_at)) AS SIGNED)
+FROM
+    pokemon_staging
+"#
+            )
+            .fetch_one(pool)
+            .await
+        }
+    }
+}
+
+fn not_modified_since(headers: &HeaderMap, last_modified: SystemTime) -> bool {
+    headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+        .is_some_and(|since| last_modified <= since)
+}
+
+pub async fn pokedex_range(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    path: &str,
+    query: &QueryMap,
+    headers: &HeaderMap,
+    include_forms: bool,
+    shape: Shape,
+    effectiveness: EffectivenessFormat,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let range = match PokedexRange::parse(
+        query.first("pokedex_from"),
+        query.first("pokedex_to"),
+        include_forms,
+        *LIST_MAX_LIMIT,
+    ) {
+        Ok(range) => range,
+        Err(message) => return ApiError::InvalidQuery(message).into_response(),
+    };
+    info!(?range, "requested a pokedex range");
+
+    let last_modified = table_last_modified(pool, table).await?;
+    if let Some(last_modified) = last_modified {
+        if not_modified_since(headers, last_modified) {
+            return Ok(response::response(304)
+                .header(
+                    LAST_MODIFIED,
+                    HeaderValue::try_from(httpdate::fmt_http_date(last_modified))?,
+                )
+                .empty());
+        }
+    }
+
+    let pokemon =
+        queries::pokedex_range(pool, table, range.from, range.to, range.include_forms).await?;
+    let links = link_header(path, query, &range.links(pokemon.is_empty()));
+    let pokemon = pokemon
+        .iter()
+        .map(|row| PokemonBody::new(row, effectiveness))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut builder = response::response(200).header(LINK, HeaderValue::try_from(links)?);
+    if let Some(last_modified) = last_modified {
+        builder = builder.header(
+            LAST_MODIFIED,
+            HeaderValue::try_from(httpdate::fmt_http_date(last_modified))?,
+        );
+    }
+    shape.list(builder, Format::from_accept(headers), &pokemon)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_support::db_test;
+
+    #[test]
+    fn parses_a_living_dex_range() {
+        assert_eq!(
+            PokedexRange::parse(Some("1"), Some("151"), false, 500),
+            Ok(PokedexRange {
+                from: 1,
+                to: 151,
+                include_forms: false
+            })
+        );
+    }
+
+    #[test]
+    fn not_modified_when_client_copy_is_current() {
+        let last_modified = UNIX_EPOCH + Duration::from_secs(1_690_000_000);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::try_from(httpdate::fmt_http_date(last_modified)).unwrap(),
+        );
+
+        assert!(not_modified_since(&headers, last_modified));
+        assert!(!not_modified_since(
+            &headers,
+            last_modified + Duration::from_secs(1)
+        ));
+        assert!(!not_modified_since(&HeaderMap::new(), last_modified));
+    }
+
+    db_test! {
+        async fn last_modified_counts_deletions(pool: MySqlPool) {
+            sqlx::query(
+                "INSERT INTO tombstones (slug, deleted_at) \
+                VALUES ('missingno', '2037-01-01 00:00:00')",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            let deleted_at: i64 = sqlx::query_scalar(
+                "SELECT CAST(UNIX_TIMESTAMP(deleted_at) AS SIGNED) FROM tombstones",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(
+                table_last_modified(&pool, PokemonTable::Pokemon)
+                    .await
+                    .unwrap(),
+                Some(UNIX_EPOCH + Duration::from_secs(deleted_at as u64))
+            );
+
+            // a database from before tombstones only counts writes
+            sqlx::query("DROP TABLE tombstones")
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let updated_at: i64 = sqlx::query_scalar(
+                "SELECT CAST(UNIX_TIMESTAMP(MAX(updated_at)) AS SIGNED) FROM pokemon",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(
+                table_last_modified(&pool, PokemonTable::Pokemon)
+                    .await
+                    .unwrap(),
+                Some(UNIX_EPOCH + Duration::from_secs(updated_at as u64))
+            );
+        }
+    }
+
+    #[test]
+    fn links_to_the_neighbouring_pages() {
+        let range = PokedexRange::parse(Some("152"), Some("251"), true, 500).unwrap();
+        let query = QueryMap::from(HashMap::from([
+            ("pokedex_from".to_string(), "152".to_string()),
+            ("pokedex_to".to_string(), "251".to_string()),
+            ("forms".to_string(), "all".to_string()),
+        ]));
+
+        assert_eq!(
+            link_header("/api/pokemon", &query, &range.links(false)),
+            "</api/pokemon?pokedex_from=1&pokedex_to=100&forms=all>; rel=\"first\", \
+            </api/pokemon?pokedex_from=52&pokedex_to=151&forms=all>; rel=\"prev\", \
+            </api/pokemon?pokedex_from=252&pokedex_to=351&forms=all>; rel=\"next\""
+        );
+        assert_eq!(
+            PokedexRange::parse(Some("1"), Some("151"), false, 500)
+                .unwrap()
+                .links(true),
+            [("first", 1, 151)]
+        );
+    }
+
+    #[test]
+    fn rejects_backwards_and_oversized_ranges() {
+        assert!(PokedexRange::parse(Some("151"), Some("1"), false, 500).is_err());
+        assert!(PokedexRange::parse(Some("1"), Some("1000"), false, 500).is_err());
+    }
+}
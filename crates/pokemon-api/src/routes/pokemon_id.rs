@@ -0,0 +1,71 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonTable;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::info;
+use upload_pokemon_data::PokemonId;
+
+use crate::{
+    pokemon_not_found,
+    response::{self, Format},
+};
+
+/// Just enough to confirm a pokemon exists, with both of its identifiers.
+#[derive(Debug, Serialize)]
+struct PokemonIdOnly {
+    id: PokemonId,
+    pokedex_id: u16,
+}
+
+/// `/api/pokemon/:slug?fields=id`, an existence check that reads only the
+/// identifier columns.
+pub async fn pokemon_id(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    info!(slug, "requested a pokemon id");
+    let found = match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_as!(
+                PokemonIdOnly,
+                r#"
+SELECT
+    id as "id!: PokemonId",
+    pokedex_id
+FROM
+    pokemon
+WHERE
+    slug = ?
+"#,
+                slug
+            )
+            .fetch_optional(pool)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query_as!(
+                PokemonIdOnly,
+                r#"
+SELECT
+    id as "id!: PokemonId",
+    pokedex_id
+FROM
+    pokemon_staging
+WHERE
+    slug = ?
+"#,
+                slug
+            )
+            .fetch_optional(pool)
+            .await?
+        }
+    };
+
+    match found {
+        Some(found) => response::response(200).format(format, &found),
+        None => pokemon_not_found(pool, table, slug).await,
+    }
+}
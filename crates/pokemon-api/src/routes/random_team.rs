@@ -0,0 +1,127 @@
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::info;
+use upload_pokemon_data::PokemonTableRow;
+
+use super::{PokemonTable, POKEMON_COLUMNS};
+use crate::{
+    effectiveness::{EffectivenessFormat, PokemonBody},
+    error::ApiError,
+    jsonapi::Shape,
+    response::{self, Format},
+};
+
+const DEFAULT_TEAM_SIZE: u8 = 6;
+/// A party holds six pokemon.
+const MAX_TEAM_SIZE: u8 = 6;
+
+fn parse_size(size: Option<&str>) -> Result<u8, ApiError> {
+    let Some(size) = size else {
+        return Ok(DEFAULT_TEAM_SIZE);
+    };
+    match size.parse() {
+        Ok(size @ 1..=MAX_TEAM_SIZE) => Ok(size),
+        _ => Err(ApiError::InvalidQuery(format!(
+            "size must be a number from 1 to {MAX_TEAM_SIZE}"
+        ))),
+    }
+}
+
+/// `None` for any pokemon, otherwise only legendaries or only the rest.
+fn parse_legendary(legendary: Option<&str>) -> Result<Option<bool>, ApiError> {
+    match legendary {
+        None => Ok(None),
+        Some("true") => Ok(Some(true)),
+        Some("false") => Ok(Some(false)),
+        Some(_) => Err(ApiError::InvalidQuery(
+            "legendary must be `true` or `false`".to_string(),
+        )),
+    }
+}
+
+/// `/api/pokemon/random-team?size=6&legendary=false`, distinct default forms
+/// picked at random. Default forms each have their own pokedex id, so no
+/// species shows up twice. Fewer than `size` come back when fewer match.
+pub async fn random_team(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    query: &QueryMap,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let (size, legendary, effectiveness, shape) = match (
+        parse_size(query.first("size")),
+        parse_legendary(query.first("legendary")),
+        EffectivenessFormat::parse(query.first("effectiveness")),
+        Shape::parse(query.first("format"), format),
+    ) {
+        (Ok(size), Ok(legendary), Ok(effectiveness), Ok(shape)) => {
+            (size, legendary, effectiveness, shape)
+        }
+        (Err(error), _, _, _)
+        | (_, Err(error), _, _)
+        | (_, _, Err(error), _)
+        | (_, _, _, Err(error)) => return error.into_response(),
+    };
+    info!(size, ?legendary, "requested a random team");
+
+    let mut team = QueryBuilder::<MySql>::new(format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} WHERE is_default"
+    ));
+    if let Some(legendary) = legendary {
+        team.push(" AND legendary_or_mythical = ")
+            .push_bind(legendary);
+    }
+    // the table is small enough that sorting all of it at random is cheap
+    team.push(" ORDER BY RAND() LIMIT ").push_bind(size);
+
+    let team = team
+        .build_query_as::<PokemonTableRow>()
+        .fetch_all(pool)
+        .await?;
+    let team = team
+        .iter()
+        .map(|row| PokemonBody::new(row, effectiveness))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    shape.list(response::response(200), format, &team)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::test_support::{body_json, db_test};
+
+    #[test]
+    fn size_defaults_to_a_full_party() {
+        assert_eq!(parse_size(None), Ok(6));
+        assert_eq!(parse_size(Some("3")), Ok(3));
+        assert!(parse_size(Some("0")).is_err());
+        assert!(parse_size(Some("7")).is_err());
+        assert!(parse_legendary(Some("maybe")).is_err());
+    }
+
+    db_test! {
+        async fn picks_distinct_pokemon_matching_the_filters(pool: MySqlPool) {
+            let query = QueryMap::from(HashMap::from([
+                ("size".to_string(), "6".to_string()),
+                ("legendary".to_string(), "false".to_string()),
+            ]));
+            let response = random_team(&pool, PokemonTable::Pokemon, &query, Format::Json)
+                .await
+                .unwrap();
+
+            let body = body_json(response);
+            let mut slugs: Vec<_> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pokemon| pokemon["slug"].as_str().unwrap())
+                .collect();
+            slugs.sort_unstable();
+            assert_eq!(slugs, ["bulbasaur", "squirtle"]);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonTable;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::info;
+use upload_pokemon_data::PokemonId;
+
+use super::fetch_base_stats;
+use crate::{
+    error::ApiError,
+    jsonapi::{Identified, Shape},
+    pokemon_not_found,
+    response::{self, Format},
+};
+
+const DEFAULT_SIMILAR: u32 = 5;
+
+#[derive(Debug, Serialize)]
+struct SimilarPokemon {
+    id: PokemonId,
+    pokedex_id: u16,
+    slug: String,
+    name: String,
+    /// Euclidean distance over the six base stats.
+    distance: f64,
+}
+
+impl Identified for SimilarPokemon {
+    fn id(&self) -> &PokemonId {
+        &self.id
+    }
+}
+
+/// How many similar pokemon to answer with, from `?n=`, capped like any
+/// other list.
+fn parse_n(n: Option<&str>, max: u32) -> Result<u32, ApiError> {
+    super::parse_limit("n", n, DEFAULT_SIMILAR, max)
+}
+
+/// `/api/pokemon/:slug/similar`, the pokemon whose base stats are closest to
+/// this one's. Other forms of the same pokedex entry are left out, and
+/// alternate forms of other entries are too unless `include_forms=true`.
+pub async fn similar(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    query: &QueryMap,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let (n, include_forms, shape) = match (
+        parse_n(query.first("n"), *super::LIST_MAX_LIMIT),
+        super::include_forms(query),
+        Shape::parse(query.first("format"), format),
+    ) {
+        (Ok(n), Ok(include_forms), Ok(shape)) => (n, include_forms, shape),
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+            return error.into_response()
+        }
+    };
+    info!(slug, n, include_forms, "requested similar pokemon");
+    let Some(stats) = fetch_base_stats(pool, table, slug).await? else {
+        return pokemon_not_found(pool, table, slug).await;
+    };
+
+    // the stat columns are unsigned, so they're cast before subtracting
+    let similar = match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_as!(
+                SimilarPokemon,
+                r#"
+SELECT
+    id as "id!: PokemonId",
+    pokedex_id,
+    slug,
+    name,
+    SQRT(
+        POW(CAST(hp AS SIGNED) - ?, 2)
+        + POW(CAST(attack AS SIGNED) - ?, 2)
+        + POW(CAST(defense AS SIGNED) - ?, 2)
+        + POW(CAST(special_attack AS SIGNED) - ?, 2)
+        + POW(CAST(special_defense AS SIGNED) - ?, 2)
+        + POW(CAST(speed AS SIGNED) - ?, 2)
+    ) as "distance!: f64"
+FROM
+    pokemon
+WHERE
+    pokedex_id <> ?
+    AND (? OR is_default)
+ORDER BY
+    distance, pokedex_id
+LIMIT ?
+"#,
+                stats.hp,
+                stats.attack,
+                stats.defense,
+                stats.special_attack,
+                stats.special_defense,
+                stats.speed,
+                stats.pokedex_id,
+                include_forms,
+                n
+            )
+            .fetch_all(pool)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query_as!(
+                SimilarPokemon,
+                r#"
+SELECT
+    id as "id!: PokemonId",
+    pokedex_id,
+    slug,
+    name,
+    SQRT(
+        POW(CAST(hp AS SIGNED) - ?, 2)
+        + POW(CAST(attack AS SIGNED) - ?, 2)
+        + POW(CAST(defense AS SIGNED) - ?, 2)
+        + POW(CAST(special_attack AS SIGNED) - ?, 2)
+        + POW(CAST(special_defense AS SIGNED) - ?, 2)
+        + POW(CAST(speed AS SIGNED) - ?, 2)
+    ) as "distance!: f64"
+FROM
+    pokemon_staging
+WHERE
+    pokedex_id <> ?
+    AND (? OR is_default)
+ORDER BY
+    distance, pokedex_id
+LIMIT ?
+"#,
+                stats.hp,
+                stats.attack,
+                stats.defense,
+                stats.special_attack,
+                stats.special_defense,
+                stats.speed,
+                stats.pokedex_id,
+                include_forms,
+                n
+            )
+            .fetch_all(pool)
+            .await?
+        }
+    };
+
+    shape.list(response::response(200), format, &similar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+
+    #[test]
+    fn n_defaults_to_five_and_is_clamped() {
+        assert_eq!(parse_n(None, 50), Ok(5));
+        assert_eq!(parse_n(Some("1"), 50), Ok(1));
+        assert_eq!(parse_n(Some("51"), 50), Ok(50));
+        assert!(parse_n(Some("0"), 50).is_err());
+        assert!(parse_n(Some("five"), 50).is_err());
+    }
+
+    db_test! {
+        async fn excludes_the_pokemon_itself(pool: MySqlPool) {
+            let response = similar(&pool, "squirtle", &QueryMap::default(), Format::Json)
+                .await
+                .unwrap();
+
+            assert_eq!(response.status_code, 200);
+            let body = body_json(response);
+            let slugs: Vec<_> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pokemon| pokemon["slug"].as_str().unwrap())
+                .collect();
+            assert_eq!(slugs, ["bulbasaur", "ho-oh"]);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use aws_lambda_events::{event::apigw::ApiGatewayProxyResponse, query_map::QueryMap};
+use lambda_runtime::Error;
+use pokemon_api::queries::PokemonTable;
+use serde::Serialize;
+use sqlx::{MySqlPool, Row};
+use tracing::info;
+use upload_pokemon_data::PokemonType;
+
+use super::distribution::STATS;
+use crate::{
+    error::ApiError,
+    response::{self, Format},
+};
+
+/// One base stat across every pokemon of a type. All zero when the type has
+/// no pokemon.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct StatSummary {
+    /// Rounded to one decimal place.
+    average: f64,
+    min: u16,
+    max: u16,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct TypeStats {
+    #[serde(rename = "type")]
+    pokemon_type: PokemonType,
+    count: i64,
+    stats: BTreeMap<&'static str, StatSummary>,
+}
+
+/// `/api/types/:type/stats`, the average, min and max of each base stat over
+/// the pokemon of one type, e.g. how bulky steel types are.
+pub async fn type_stats(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    pokemon_type: &str,
+    query: &QueryMap,
+    format: Format,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    let Ok(pokemon_type) = pokemon_type.parse::<PokemonType>() else {
+        return ApiError::TypeNotFound.into_response();
+    };
+    let include_forms = match super::include_forms(query) {
+        Ok(include_forms) => include_forms,
+        Err(error) => return error.into_response(),
+    };
+    info!(%pokemon_type, include_forms, "requested type stats");
+
+    // only the fixed `STATS` names are written into the query
+    let aggregates: Vec<String> = STATS
+        .iter()
+        .map(|stat| {
+            format!("CAST(AVG(pokemon.{stat}) AS DOUBLE), MIN(pokemon.{stat}), MAX(pokemon.{stat})")
+        })
+        .collect();
+    let row = sqlx::query(&format!(
+        "SELECT COUNT(*), {} FROM {table} AS pokemon \
+        JOIN typing ON typing.pokemon_id = pokemon.id \
+        WHERE typing.typing = ? AND (? OR pokemon.is_default)",
+        aggregates.join(", ")
+    ))
+    .bind(pokemon_type)
+    .bind(include_forms)
+    .fetch_one(pool)
+    .await?;
+
+    let mut stats = BTreeMap::new();
+    for (index, stat) in STATS.into_iter().enumerate() {
+        let column = 1 + index * 3;
+        let average: Option<f64> = row.try_get(column)?;
+        let min: Option<u16> = row.try_get(column + 1)?;
+        let max: Option<u16> = row.try_get(column + 2)?;
+        stats.insert(
+            stat,
+            StatSummary {
+                average: average.map_or(0.0, |average| (average * 10.0).round() / 10.0),
+                min: min.unwrap_or_default(),
+                max: max.unwrap_or_default(),
+            },
+        );
+    }
+    response::response(200).format(
+        format,
+        &TypeStats {
+            pokemon_type,
+            count: row.try_get(0)?,
+            stats,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+    use serde_json::{json, Value};
+
+    async fn stats_for(pool: &MySqlPool, pokemon_type: &str) -> (i64, Value) {
+        let response = type_stats(
+            pool,
+            PokemonTable::Pokemon,
+            pokemon_type,
+            &QueryMap::default(),
+            Format::Json,
+        )
+        .await
+        .unwrap();
+        (response.status_code, body_json(response))
+    }
+
+    db_test! {
+        async fn aggregates_each_stat_for_a_type(pool: MySqlPool) {
+            let (status, water) = stats_for(&pool, "water").await;
+            assert_eq!(status, 200);
+            assert_eq!(water["type"], "water");
+            assert_eq!(water["count"], 1);
+            assert_eq!(
+                water["stats"]["hp"],
+                json!({ "average": 44.0, "min": 44, "max": 44 })
+            );
+
+            let (status, dragon) = stats_for(&pool, "Dragon").await;
+            assert_eq!(status, 200);
+            assert_eq!(dragon["count"], 0);
+            assert_eq!(
+                dragon["stats"]["speed"],
+                json!({ "average": 0.0, "min": 0, "max": 0 })
+            );
+
+            let (status, unknown) = stats_for(&pool, "sound").await;
+            assert_eq!(status, 404);
+            assert_eq!(unknown["code"], "TYPE_NOT_FOUND");
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use http::header::HeaderMap;
+use lambda_runtime::Error;
+use pokemon_api::queries::{self, PokemonTable};
+use serde_json::{Map, Value};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row, Transaction};
+use tracing::{info, warn};
+
+use super::admin::is_admin;
+use crate::{cache, error::ApiError, response};
+
+/// What a writable column accepts.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Int { min: u16, max: u16 },
+    Float { min: f32, max: f32, nullable: bool },
+    Bool,
+    Text { max_len: usize },
+}
+
+const STAT: Kind = Kind::Int { min: 1, max: 255 };
+const BYTE: Kind = Kind::Int { min: 0, max: 255 };
+const SMALLINT: Kind = Kind::Int {
+    min: 0,
+    max: u16::MAX,
+};
+const EFFECTIVENESS: Kind = Kind::Float {
+    min: 0.0,
+    max: 4.0,
+    nullable: false,
+};
+
+/// The columns a correction can change. Ids, slugs and which form is the
+/// default are what other rows and tables hang off, so they only change by
+/// re-running the uploader.
+const WRITABLE: &[(&str, Kind)] = &[
+    ("name", Kind::Text { max_len: 30 }),
+    ("hp", STAT),
+    ("attack", STAT),
+    ("defense", STAT),
+    ("special_attack", STAT),
+    ("special_defense", STAT),
+    ("speed", STAT),
+    ("height", SMALLINT),
+    ("weight", SMALLINT),
+    ("generation", Kind::Int { min: 1, max: 9 }),
+    (
+        "female_rate",
+        Kind::Float {
+            min: 0.0,
+            max: 1.0,
+            nullable: true,
+        },
+    ),
+    ("genderless", Kind::Bool),
+    ("legendary_or_mythical", Kind::Bool),
+    ("forms_switchable", Kind::Bool),
+    ("base_experience", SMALLINT),
+    ("capture_rate", BYTE),
+    ("base_happiness", BYTE),
+    ("primary_color", Kind::Text { max_len: 6 }),
+    ("normal_attack_effectiveness", EFFECTIVENESS),
+    ("fire_attack_effectiveness", EFFECTIVENESS),
+    ("water_attack_effectiveness", EFFECTIVENESS),
+    ("electric_attack_effectiveness", EFFECTIVENESS),
+    ("grass_attack_effectiveness", EFFECTIVENESS),
+    ("ice_attack_effectiveness", EFFECTIVENESS),
+    ("fighting_attack_effectiveness", EFFECTIVENESS),
+    ("poison_attack_effectiveness", EFFECTIVENESS),
+    ("ground_attack_effectiveness", EFFECTIVENESS),
+    ("fly_attack_effectiveness", EFFECTIVENESS),
+    ("psychic_attack_effectiveness", EFFECTIVENESS),
+    ("bug_attack_effectiveness", EFFECTIVENESS),
+    ("rock_attack_effectiveness", EFFECTIVENESS),
+    ("ghost_attack_effectiveness", EFFECTIVENESS),
+    ("dragon_attack_effectiveness", EFFECTIVENESS),
+    ("dark_attack_effectiveness", EFFECTIVENESS),
+    ("steel_attack_effectiveness", EFFECTIVENESS),
+    ("fairy_attack_effectiveness", EFFECTIVENESS),
+];
+
+/// A validated new value for one column.
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Int(u16),
+    Float(Option<f32>),
+    Bool(bool),
+    Text(String),
+}
+
+/// Checks every field of a PATCH body against `WRITABLE`, reporting the
+/// first one that isn't writable or is out of range.
+fn parse_changes(body: &str) -> Result<Vec<(&'static str, Change)>, ApiError> {
+    let fields: Map<String, Value> = serde_json::from_str(body)
+        .map_err(|_| ApiError::InvalidBody("body must be a JSON object".to_string()))?;
+    if fields.is_empty() {
+        return Err(ApiError::InvalidBody(
+            "body must set at least one field".to_string(),
+        ));
+    }
+    fields
+        .iter()
+        .map(|(field, value)| {
+            let Some(&(column, kind)) = WRITABLE.iter().find(|(column, _)| column == field) else {
+                return Err(ApiError::InvalidBody(format!(
+                    "`{field}` is not a writable field"
+                )));
+            };
+            parse_change(kind, value)
+                .map(|change| (column, change))
+                .ok_or_else(|| ApiError::InvalidBody(format!("`{field}` {}", kind.expected())))
+        })
+        .collect()
+}
+
+fn parse_change(kind: Kind, value: &Value) -> Option<Change> {
+    match (kind, value) {
+        (Kind::Int { min, max }, Value::Number(number)) => number
+            .as_u64()
+            .and_then(|number| u16::try_from(number).ok())
+            .filter(|number| (min..=max).contains(number))
+            .map(Change::Int),
+        (Kind::Float { nullable: true, .. }, Value::Null) => Some(Change::Float(None)),
+        (Kind::Float { min, max, .. }, Value::Number(number)) => number
+            .as_f64()
+            .map(|number| number as f32)
+            .filter(|number| (min..=max).contains(number))
+            .map(|number| Change::Float(Some(number))),
+        (Kind::Bool, Value::Bool(value)) => Some(Change::Bool(*value)),
+        (Kind::Text { max_len }, Value::String(text)) => {
+            let text = text.trim();
+            (!text.is_empty() && text.chars().count() <= max_len)
+                .then(|| Change::Text(text.to_string()))
+        }
+        _ => None,
+    }
+}
+
+impl Kind {
+    fn expected(self) -> String {
+        match self {
+            Kind::Int { min, max } => format!("must be a whole number from {min} to {max}"),
+            Kind::Float { min, max, nullable } => format!(
+                "must be a number from {min} to {max}{}",
+                if nullable { " or null" } else { "" }
+            ),
+            Kind::Bool => "must be true or false".to_string(),
+            Kind::Text { max_len } => format!("must be a string of 1 to {max_len} characters"),
+        }
+    }
+}
+
+/// The stored value of each column in `changes`, or `None` when there's no
+/// pokemon with this slug. The row is locked until `tx` ends, so nothing can
+/// change it between this read and the update.
+async fn stored_values(
+    tx: &mut Transaction<'_, MySql>,
+    table: PokemonTable,
+    slug: &str,
+    changes: &[(&'static str, Change)],
+) -> Result<Option<Vec<Change>>, sqlx::Error> {
+    let mut query = QueryBuilder::<MySql>::new("SELECT ");
+    let mut separated = query.separated(", ");
+    for (column, _) in changes {
+        separated.push(*column);
+    }
+    query
+        .push(format!(" FROM {table} WHERE slug = "))
+        .push_bind(slug)
+        .push(" FOR UPDATE");
+    let Some(row) = query.build().fetch_optional(&mut **tx).await? else {
+        return Ok(None);
+    };
+    changes
+        .iter()
+        .enumerate()
+        .map(|(index, (_, change))| {
+            Ok(match change {
+                Change::Int(_) => Change::Int(row.try_get(index)?),
+                Change::Float(_) => Change::Float(row.try_get(index)?),
+                Change::Bool(_) => Change::Bool(row.try_get(index)?),
+                Change::Text(_) => Change::Text(row.try_get(index)?),
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map(Some)
+}
+
+/// The columns whose new value differs from the stored one.
+fn changed_columns(changes: &[(&'static str, Change)], stored: &[Change]) -> Vec<&'static str> {
+    changes
+        .iter()
+        .zip(stored)
+        .filter(|((_, change), stored)| change != *stored)
+        .map(|((column, _), _)| *column)
+        .collect()
+}
+
+/// `PATCH /api/pokemon/:slug`, an admin correction to some of a pokemon's
+/// fields. Answers with the updated row, plus `changed`: the fields whose
+/// value actually differs from what was stored.
+pub async fn update_pokemon(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    body: Option<&str>,
+    headers: &HeaderMap,
+    admin_token: Option<&str>,
+) -> Result<ApiGatewayProxyResponse, Error> {
+    if !is_admin(headers, admin_token) {
+        warn!("rejected an admin request");
+        return ApiError::Unauthorized.into_response();
+    }
+    let changes = match parse_changes(body.unwrap_or_default()) {
+        Ok(changes) => changes,
+        Err(error) => return error.into_response(),
+    };
+
+    // MySQL has no `RETURNING`, so the old values are read, and the row
+    // locked, in the same transaction as the update
+    let mut tx = pool.begin().await?;
+    let Some(stored) = stored_values(&mut tx, table, slug, &changes).await? else {
+        return ApiError::PokemonNotFound.into_response();
+    };
+    let changed = changed_columns(&changes, &stored);
+    info!(
+        slug,
+        ?changed,
+        unchanged = changes.len() - changed.len(),
+        "updating a pokemon"
+    );
+
+    let mut query = QueryBuilder::<MySql>::new(format!("UPDATE {table} SET "));
+    let mut separated = query.separated(", ");
+    for (column, change) in changes {
+        separated.push(format!("{column} = "));
+        match change {
+            Change::Int(value) => separated.push_bind_unseparated(value),
+            Change::Float(value) => separated.push_bind_unseparated(value),
+            Change::Bool(value) => separated.push_bind_unseparated(value),
+            Change::Text(value) => separated.push_bind_unseparated(value),
+        };
+    }
+    // set even when nothing changed, so the correction is still visible
+    separated.push("updated_at = CURRENT_TIMESTAMP");
+    query.push(" WHERE slug = ").push_bind(slug);
+    query.build().execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    if let Some(cache) = cache::response_cache() {
+        cache.forget(slug);
+    }
+    match queries::pokemon_by_slugs(pool, table, &[slug]).await?.pop() {
+        Some(pokemon) => {
+            let mut body = serde_json::to_value(pokemon)?;
+            body["changed"] = changed.into();
+            response::response(200).json(&body)
+        }
+        None => ApiError::PokemonNotFound.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{body_json, db_test};
+    use upload_pokemon_data::Stat;
+
+    #[test]
+    fn validates_fields_and_values() {
+        // serde_json's map iterates its keys in sorted order
+        assert_eq!(
+            parse_changes(r#"{"hp": 50, "female_rate": null, "name": " Mew "}"#).unwrap(),
+            [
+                ("female_rate", Change::Float(None)),
+                ("hp", Change::Int(50)),
+                ("name", Change::Text("Mew".to_string())),
+            ]
+        );
+        assert_eq!(
+            parse_changes(r#"{"slug": "mew"}"#).unwrap_err(),
+            ApiError::InvalidBody("`slug` is not a writable field".to_string())
+        );
+        assert_eq!(
+            parse_changes(r#"{"hp": 256}"#).unwrap_err(),
+            ApiError::InvalidBody("`hp` must be a whole number from 1 to 255".to_string())
+        );
+        assert_eq!(
+            parse_changes(r#"{"legendary_or_mythical": "yes"}"#).unwrap_err(),
+            ApiError::InvalidBody("`legendary_or_mythical` must be true or false".to_string())
+        );
+        assert!(parse_changes("{}").is_err());
+        assert!(parse_changes("[]").is_err());
+    }
+
+    db_test! {
+        async fn updates_only_the_given_fields(pool: MySqlPool) {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                http::header::AUTHORIZATION,
+                http::header::HeaderValue::from_static("Bearer hunter2"),
+            );
+
+            let response = update_pokemon(
+                &pool,
+                PokemonTable::Pokemon,
+                "squirtle",
+                Some(r#"{"hp": 50, "attack": 48, "name": "Squirtle"}"#),
+                &headers,
+                Some("hunter2"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status_code, 200);
+            let body = body_json(response);
+            assert_eq!(body["changed"], serde_json::json!(["hp"]));
+            let squirtle = queries::pokemon_by_slugs(&pool, PokemonTable::Pokemon, &["squirtle"])
+                .await
+                .unwrap()
+                .pop()
+                .unwrap();
+            assert_eq!(squirtle.hp, Stat(50));
+            assert_eq!(squirtle.attack, Stat(48));
+
+            let response = update_pokemon(
+                &pool,
+                PokemonTable::Pokemon,
+                "squirtle",
+                Some(r#"{"hp": 50}"#),
+                &HeaderMap::new(),
+                Some("hunter2"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status_code, 401);
+            let response = update_pokemon(
+                &pool,
+                PokemonTable::Pokemon,
+                "squirtle",
+                Some(r#"{"hp": 50}"#),
+                &headers,
+                None,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status_code, 401);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use pokemon_api::queries::PokemonTable;
+use sqlx::MySqlPool;
+
+/// Most suggestions a 404 offers.
+const MAX_SUGGESTIONS: usize = 3;
+/// Slugs further than this many edits away aren't worth suggesting.
+const MAX_DISTANCE: usize = 3;
+/// How long a warm instance reuses the slug list before reading it again, so
+/// suggestions catch up with an upload within a minute.
+const SLUGS_TTL: Duration = Duration::from_secs(60);
+
+static SLUG_CACHE: Lazy<SlugCache> = Lazy::new(SlugCache::default);
+
+/// The stored slugs of each table, and when they were read. The lock is
+/// never held across an `.await`.
+#[derive(Debug, Default)]
+struct SlugCache {
+    tables: Mutex<HashMap<&'static str, (Instant, Arc<[String]>)>>,
+}
+
+impl SlugCache {
+    fn get(&self, table: PokemonTable, now: Instant) -> Option<Arc<[String]>> {
+        let tables = self
+            .tables
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        tables
+            .get(table.name())
+            .filter(|(read_at, _)| now.duration_since(*read_at) < SLUGS_TTL)
+            .map(|(_, slugs)| Arc::clone(slugs))
+    }
+
+    fn put(&self, table: PokemonTable, slugs: Arc<[String]>, now: Instant) {
+        let mut tables = self
+            .tables
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        tables.insert(table.name(), (now, slugs));
+    }
+}
+
+/// The form slugs are stored in: lowercase, with spaces as hyphens.
+pub fn normalize_slug(slug: &str) -> String {
+    slug.trim().to_lowercase().replace(' ', "-")
+}
+
+/// Up to three stored slugs closest to `slug` by edit distance.
+pub async fn suggest_slugs(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let slugs = match SLUG_CACHE.get(table, Instant::now()) {
+        Some(slugs) => slugs,
+        None => {
+            let slugs: Arc<[String]> = stored_slugs(pool, table).await?.into();
+            SLUG_CACHE.put(table, Arc::clone(&slugs), Instant::now());
+            slugs
+        }
+    };
+    Ok(closest(slug, &slugs))
+}
+
+async fn stored_slugs(pool: &MySqlPool, table: PokemonTable) -> Result<Vec<String>, sqlx::Error> {
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query_scalar!("SELECT slug FROM pokemon")
+                .fetch_all(pool)
+                .await
+        }
+        PokemonTable::Staging => {
+            sqlx::query_scalar!("SELECT slug FROM pokemon_staging")
+                .fetch_all(pool)
+                .await
+        }
+    }
+}
+
+fn closest(slug: &str, candidates: &[String]) -> Vec<String> {
+    let mut scored: Vec<(usize, &String)> = candidates
+        .iter()
+        .map(|candidate| (levenshtein(slug, candidate), candidate))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        .collect();
+    scored.sort_unstable();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(levenshtein("squirtle", "squirtle"), 0);
+        assert_eq!(levenshtein("squirtel", "squirtle"), 2);
+        assert_eq!(levenshtein("pikachu", "raichu"), 4);
+        assert_eq!(levenshtein("", "mew"), 3);
+    }
+
+    #[test]
+    fn suggests_the_closest_few_slugs() {
+        let slugs = ["bulbasaur", "ivysaur", "venusaur", "squirtle", "ho-oh"]
+            .map(String::from)
+            .to_vec();
+
+        assert_eq!(closest("bulbsaur", &slugs), ["bulbasaur"]);
+        assert_eq!(closest("ivysaurs", &slugs), ["ivysaur"]);
+        assert!(closest("missingno", &slugs).is_empty());
+        // nearest first, ties in slug order
+        assert_eq!(
+            closest("pichu", &["raichu", "pikachu", "pichu"].map(String::from)),
+            ["pichu", "pikachu", "raichu"]
+        );
+        assert_eq!(normalize_slug(" Ho Oh "), "ho-oh");
+    }
+
+    #[test]
+    fn slug_lists_are_reused_until_they_expire() {
+        let cache = SlugCache::default();
+        let read_at = Instant::now();
+        cache.put(PokemonTable::Pokemon, ["pichu".to_string()].into(), read_at);
+
+        assert_eq!(
+            cache
+                .get(PokemonTable::Pokemon, read_at + SLUGS_TTL / 2)
+                .as_deref(),
+            Some(&["pichu".to_string()][..])
+        );
+        assert!(cache
+            .get(PokemonTable::Pokemon, read_at + SLUGS_TTL)
+            .is_none());
+        assert!(cache.get(PokemonTable::Staging, read_at).is_none());
+    }
+}
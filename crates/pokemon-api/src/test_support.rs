@@ -0,0 +1,42 @@
+//! Helpers shared by the unit tests.
+
+use aws_lambda_events::{encodings::Body, event::apigw::ApiGatewayProxyResponse};
+use sqlx::{Executor, MySqlPool};
+
+/// The JSON a response carries, panicking unless it has a text body.
+pub fn body_json(response: ApiGatewayProxyResponse) -> serde_json::Value {
+    match response.body {
+        Some(Body::Text(body)) => serde_json::from_str(&body).unwrap(),
+        body => panic!("expected a text body, got {body:?}"),
+    }
+}
+
+/// A `#[sqlx::test]` against a throwaway database loaded by
+/// `load_fixtures`. It's reported as ignored unless the `db-tests` feature
+/// is on, since it needs a MySQL server at `DATABASE_URL`.
+macro_rules! db_test {
+    (async fn $name:ident($pool:ident: $pool_type:ty) $body:block) => {
+        #[sqlx::test(migrations = false)]
+        #[cfg_attr(
+            not(feature = "db-tests"),
+            ignore = "set DATABASE_URL and enable db-tests"
+        )]
+        async fn $name($pool: $pool_type) {
+            $crate::test_support::load_fixtures(&$pool).await;
+            $body
+        }
+    };
+}
+pub(crate) use db_test;
+
+/// Creates the uploader's tables and loads Bulbasaur, Squirtle and Ho-Oh.
+/// Read from here rather than with `#[sqlx::test(fixtures(...))]`, whose
+/// paths would be relative to each test's own file.
+pub async fn load_fixtures(pool: &MySqlPool) {
+    for fixture in [
+        include_str!("../../upload-pokemon-data/create-tables.sql"),
+        include_str!("fixtures/pokemon.sql"),
+    ] {
+        pool.execute(fixture).await.unwrap();
+    }
+}
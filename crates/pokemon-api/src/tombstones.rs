@@ -0,0 +1,53 @@
+use sqlx::{mysql::MySqlDatabaseError, MySqlPool};
+use tracing::warn;
+
+/// `ER_NO_SUCH_TABLE`, raised when `tombstones` hasn't been created yet.
+const ER_NO_SUCH_TABLE: u16 = 1146;
+
+/// A slug that used to resolve to a pokemon. `redirect_to` holds the slug it
+/// was renamed to, or `None` when the pokemon was removed outright.
+#[derive(Debug)]
+pub struct Tombstone {
+    pub redirect_to: Option<String>,
+}
+
+/// Looks up the tombstone left for `slug`. A database that predates the
+/// `tombstones` table has no tombstones, so a missing table is `Ok(None)`
+/// rather than an error.
+pub async fn find_tombstone(
+    pool: &MySqlPool,
+    slug: &str,
+) -> Result<Option<Tombstone>, sqlx::Error> {
+    let result = sqlx::query_as!(
+        Tombstone,
+        r#"
+SELECT
+    redirect_to
+FROM
+    tombstones
+WHERE
+    slug = ?
+"#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Err(error) if is_missing_table_error(&error) => {
+            warn!("tombstones table does not exist; run create-tables.sql");
+            Ok(None)
+        }
+        result => result,
+    }
+}
+
+/// Whether `error` is MySQL saying a table doesn't exist.
+pub fn is_missing_table_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(error) => error
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .is_some_and(|error| error.number() == ER_NO_SUCH_TABLE),
+        _ => false,
+    }
+}
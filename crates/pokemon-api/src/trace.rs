@@ -0,0 +1,55 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::header::HeaderMap;
+
+/// Header API Gateway uses to carry the X-Ray trace id.
+pub const TRACE_ID_HEADER: &str = "x-amzn-trace-id";
+
+/// The incoming X-Ray trace id, or a freshly generated root id when the
+/// request did not come with one (e.g. invoked directly).
+pub fn trace_id(headers: &HeaderMap) -> String {
+    headers
+        .get(TRACE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(generate_trace_id)
+}
+
+/// `Root=1-{epoch seconds as 8 hex digits}-{96 random bits as 24 hex digits}`
+fn generate_trace_id() -> String {
+    let epoch_seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let random = rand::random::<u128>() >> 32;
+    format!("Root=1-{epoch_seconds:08x}-{random:024x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn uses_the_incoming_trace_id() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TRACE_ID_HEADER,
+            HeaderValue::from_static("Root=1-5759e988-bd862e3fe1be46a994272793"),
+        );
+
+        assert_eq!(
+            trace_id(&headers),
+            "Root=1-5759e988-bd862e3fe1be46a994272793"
+        );
+    }
+
+    #[test]
+    fn generates_a_root_trace_id_when_missing() {
+        let trace_id = trace_id(&HeaderMap::new());
+
+        assert!(trace_id.starts_with("Root=1-"));
+        assert_eq!(trace_id.len(), "Root=1-".len() + 8 + 1 + 24);
+    }
+}
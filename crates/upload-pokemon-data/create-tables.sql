@@ -2,6 +2,10 @@ CREATE TABLE IF NOT EXISTS pokemon(
     id VARBINARY(27) NOT NULL, -- ksuid
     slug VARCHAR(30) NOT NULL, -- generated
     name VARCHAR(30) NOT NULL,
+    name_ja VARCHAR(30), -- localized names, NULL when the dataset has none
+    name_fr VARCHAR(30),
+    name_de VARCHAR(30),
+    name_es VARCHAR(30),
     pokedex_id SMALLINT UNSIGNED NOT NULL,
     -- abilities -- new table
     -- typing -- new table
@@ -11,6 +15,10 @@ CREATE TABLE IF NOT EXISTS pokemon(
     special_attack SMALLINT UNSIGNED NOT NULL,
     special_defense SMALLINT UNSIGNED NOT NULL,
     speed SMALLINT UNSIGNED NOT NULL,
+    -- kept by MySQL so it can be indexed, see `add-base-stat-total`
+    base_stat_total SMALLINT UNSIGNED GENERATED ALWAYS AS (
+        hp + attack + defense + special_attack + special_defense + speed
+    ) STORED,
     height SMALLINT UNSIGNED NOT NULL,
     weight SMALLINT UNSIGNED NOT NULL,
     generation SMALLINT UNSIGNED NOT NULL,
@@ -44,10 +52,16 @@ CREATE TABLE IF NOT EXISTS pokemon(
     dark_attack_effectiveness FLOAT NOT NULL,
     steel_attack_effectiveness FLOAT NOT NULL,
     fairy_attack_effectiveness FLOAT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
     PRIMARY KEY ( id ),
-    UNIQUE ( slug )
+    UNIQUE ( slug ),
+    INDEX base_stat_total ( base_stat_total )
 );
 
+-- same shape as `pokemon`, for the API to read when `POKEMON_TABLE=pokemon_staging`
+CREATE TABLE IF NOT EXISTS pokemon_staging LIKE pokemon;
+
 CREATE TABLE IF NOT EXISTS abilities(
     id VARBINARY(27) NOT NULL, -- ksuid
     pokemon_id VARBINARY(27) NOT NULL,
@@ -75,3 +89,10 @@ CREATE TABLE IF NOT EXISTS evolutions(
     evolves_from VARBINARY(27) NOT NULL,
     PRIMARY KEY ( id )
 );
+
+CREATE TABLE IF NOT EXISTS tombstones(
+    slug VARCHAR(30) NOT NULL, -- slug that no longer resolves
+    redirect_to VARCHAR(30), -- renamed slug, NULL when the pokemon was removed
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ( slug )
+);
@@ -0,0 +1,40 @@
+use color_eyre::eyre;
+use sqlx::MySqlPool;
+use tracing::info;
+
+/// Adds the `base_stat_total` column, and an index on it, to a `pokemon`
+/// table created before `create-tables.sql` had it. Safe to run again.
+///
+/// It's a `STORED` generated column rather than one the uploader fills in:
+/// MySQL keeps it right through admin corrections and any other write that
+/// doesn't go through the uploader, and an index on it lets "top by total"
+/// read in order instead of sorting an expression. The cost is that the
+/// `ALTER` rewrites the table once, which at ~1000 rows is nothing.
+pub async fn add_base_stat_total(pool: MySqlPool) -> eyre::Result<()> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM information_schema.COLUMNS \
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'pokemon' \
+        AND COLUMN_NAME = 'base_stat_total'",
+    )
+    .fetch_one(&pool)
+    .await?;
+    if exists {
+        println!("pokemon.base_stat_total already exists, nothing to do");
+        return Ok(());
+    }
+
+    info!("adding pokemon.base_stat_total");
+    // must match the definition in create-tables.sql
+    sqlx::query(
+        "ALTER TABLE pokemon \
+        ADD COLUMN base_stat_total SMALLINT UNSIGNED GENERATED ALWAYS AS \
+        (hp + attack + defense + special_attack + special_defense + speed) STORED \
+        AFTER speed, \
+        ADD INDEX base_stat_total (base_stat_total)",
+    )
+    .execute(&pool)
+    .await?;
+
+    println!("added pokemon.base_stat_total");
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use color_eyre::eyre;
+use sqlx::MySqlPool;
+use tracing::info;
+
+/// The columns `create-tables.sql` gained along with `Last-Modified`, with
+/// the definitions they have there. `pokemon_staging` is created
+/// `LIKE pokemon`, so one made from an old `pokemon` is missing them too.
+const COLUMNS: [(&str, &str, &str); 5] = [
+    (
+        "pokemon",
+        "created_at",
+        "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+    ),
+    (
+        "pokemon",
+        "updated_at",
+        "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP",
+    ),
+    (
+        "pokemon_staging",
+        "created_at",
+        "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+    ),
+    (
+        "pokemon_staging",
+        "updated_at",
+        "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP",
+    ),
+    (
+        "tombstones",
+        "deleted_at",
+        "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+    ),
+];
+
+/// Adds `created_at` and `updated_at` to the pokemon tables, and
+/// `deleted_at` to `tombstones`, where they were created before
+/// `create-tables.sql` had them. Safe to run again.
+///
+/// Existing rows get the time of the upgrade, so the first `Last-Modified`
+/// after it is the upgrade rather than the last real change.
+pub async fn add_change_timestamps(pool: MySqlPool) -> eyre::Result<()> {
+    for (table, column, definition) in COLUMNS {
+        let table_exists: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM information_schema.TABLES \
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?",
+        )
+        .bind(table)
+        .fetch_one(&pool)
+        .await?;
+        if !table_exists {
+            println!("{table} doesn't exist, create-tables.sql creates it with {column}");
+            continue;
+        }
+
+        let exists: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM information_schema.COLUMNS \
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?",
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(&pool)
+        .await?;
+        if exists {
+            println!("{table}.{column} already exists, nothing to do");
+            continue;
+        }
+
+        info!(table, column, "adding column");
+        sqlx::query(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))
+        .execute(&pool)
+        .await?;
+        println!("added {table}.{column}");
+    }
+    Ok(())
+}
@@ -1,34 +1,128 @@
-use crate::pokemon_csv::PokemonCsv;
+use crate::{pokemon_csv::PokemonCsv, pokemon_type::PokemonType};
+use chrono::NaiveDate;
 use inflector::Inflector;
-use serde::{Serialize, Serializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sqlx::{
     database::{HasArguments, HasValueRef},
     encode::IsNull,
     mysql::MySqlTypeInfo,
-    Database, Encode, Decode, MySql, MySqlPool, Type,
+    Database, Decode, Encode, MySql, MySqlPool, Transaction, Type,
 };
+use std::{collections::BTreeMap, env, fmt, future::Future, str::FromStr};
 use svix_ksuid::{Ksuid, KsuidLike};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PokemonId(Ksuid);
 
-#[derive(Debug, Clone)]
+/// One of the six base stats, kept apart from the other `u16` columns so
+/// passing a stat where a height, weight or pokedex id is expected doesn't
+/// compile. Stored and serialized as the bare number.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, sqlx::Type,
+)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct Stat(pub u16);
+
+impl From<u8> for Stat {
+    fn from(value: u8) -> Self {
+        Stat(value.into())
+    }
+}
+
+impl From<u16> for Stat {
+    fn from(value: u16) -> Self {
+        Stat(value)
+    }
+}
+
+impl From<Stat> for u16 {
+    fn from(Stat(value): Stat) -> Self {
+        value
+    }
+}
+
+/// Every column of the `pokemon` table, for queries built at runtime that
+/// decode into `PokemonTableRow`.
+pub const POKEMON_COLUMNS: &str = "\
+id, name, slug, pokedex_id, hp, attack, defense, special_attack, special_defense, speed, \
+height, weight, generation, female_rate, genderless, legendary_or_mythical, is_default, \
+forms_switchable, base_experience, capture_rate, base_happiness, primary_color, \
+number_pokemon_with_typing, normal_attack_effectiveness, fire_attack_effectiveness, \
+water_attack_effectiveness, electric_attack_effectiveness, grass_attack_effectiveness, \
+ice_attack_effectiveness, fighting_attack_effectiveness, poison_attack_effectiveness, \
+ground_attack_effectiveness, fly_attack_effectiveness, psychic_attack_effectiveness, \
+bug_attack_effectiveness, rock_attack_effectiveness, ghost_attack_effectiveness, \
+dragon_attack_effectiveness, dark_attack_effectiveness, steel_attack_effectiveness, \
+fairy_attack_effectiveness";
+
+/// The table pokemon rows are read from and written to. The API picks one
+/// with `POKEMON_TABLE`, so a deployment can serve `pokemon_staging`. The
+/// uploader's `--table` only takes `pokemon` until the child tables have
+/// staging copies too.
+///
+/// The table name can't be a bound parameter, so checked queries `match` on
+/// the table with one `query!` per variant, and queries built at runtime
+/// format in the variant's name, never the raw variable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PokemonTable {
+    #[default]
+    Pokemon,
+    Staging,
+}
+
+impl PokemonTable {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pokemon" => Some(PokemonTable::Pokemon),
+            "pokemon_staging" => Some(PokemonTable::Staging),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PokemonTable::Pokemon => "pokemon",
+            PokemonTable::Staging => "pokemon_staging",
+        }
+    }
+
+    /// `pokemon` when the variable is unset. Checked when the lambda starts,
+    /// so a typo fails the deploy instead of every request.
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("POKEMON_TABLE") {
+            Ok(name) => PokemonTable::parse(&name).ok_or_else(|| {
+                format!("POKEMON_TABLE must be `pokemon` or `pokemon_staging`, got `{name}`")
+            }),
+            Err(_) => Ok(PokemonTable::default()),
+        }
+    }
+}
+
+impl fmt::Display for PokemonTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 pub struct PokemonTableRow {
     pub id: PokemonId,
     pub name: String,
     pub slug: String,
     pub pokedex_id: u16,
     // abilities: Vec<String>,
-    // typing: Vec<String>,
-    pub hp: u16,
-    pub attack: u16,
-    pub defense: u16,
-    pub special_attack: u16,
-    pub special_defense: u16,
-    pub speed: u16,
+    // typing: Vec<PokemonType>,
+    pub hp: Stat,
+    pub attack: Stat,
+    pub defense: Stat,
+    pub special_attack: Stat,
+    pub special_defense: Stat,
+    pub speed: Stat,
     pub height: u16,
     pub weight: u16,
     pub generation: u16,
+    #[serde(serialize_with = "serialize_optional_float")]
     pub female_rate: Option<f32>,
     pub genderless: bool,
     pub legendary_or_mythical: bool,
@@ -40,31 +134,104 @@ pub struct PokemonTableRow {
     pub base_happiness: u16,
     // evolves_from: Option<String>,
     pub primary_color: String,
+    #[serde(serialize_with = "serialize_float")]
     pub number_pokemon_with_typing: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub normal_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub fire_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub water_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub electric_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub grass_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub ice_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub fighting_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub poison_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub ground_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub fly_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub psychic_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub bug_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub rock_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub ghost_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub dragon_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub dark_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub steel_attack_effectiveness: f32,
+    #[serde(serialize_with = "serialize_effectiveness")]
     pub fairy_attack_effectiveness: f32,
 }
 
+/// A blank pokemon for tests to fill in with `..Default::default()`: a fresh
+/// id, zeroed stats and neutral (`1.0`) effectiveness against every type.
+impl Default for PokemonTableRow {
+    fn default() -> Self {
+        PokemonTableRow {
+            id: PokemonId::new(),
+            name: String::new(),
+            slug: String::new(),
+            pokedex_id: 0,
+            hp: Stat(0),
+            attack: Stat(0),
+            defense: Stat(0),
+            special_attack: Stat(0),
+            special_defense: Stat(0),
+            speed: Stat(0),
+            height: 0,
+            weight: 0,
+            generation: 0,
+            female_rate: None,
+            genderless: false,
+            legendary_or_mythical: false,
+            is_default: true,
+            forms_switchable: false,
+            base_experience: 0,
+            capture_rate: 0,
+            base_happiness: 0,
+            primary_color: String::new(),
+            number_pokemon_with_typing: 0.0,
+            normal_attack_effectiveness: 1.0,
+            fire_attack_effectiveness: 1.0,
+            water_attack_effectiveness: 1.0,
+            electric_attack_effectiveness: 1.0,
+            grass_attack_effectiveness: 1.0,
+            ice_attack_effectiveness: 1.0,
+            fighting_attack_effectiveness: 1.0,
+            poison_attack_effectiveness: 1.0,
+            ground_attack_effectiveness: 1.0,
+            fly_attack_effectiveness: 1.0,
+            psychic_attack_effectiveness: 1.0,
+            bug_attack_effectiveness: 1.0,
+            rock_attack_effectiveness: 1.0,
+            ghost_attack_effectiveness: 1.0,
+            dragon_attack_effectiveness: 1.0,
+            dark_attack_effectiveness: 1.0,
+            steel_attack_effectiveness: 1.0,
+            fairy_attack_effectiveness: 1.0,
+        }
+    }
+}
+
 impl From<PokemonCsv> for PokemonTableRow {
     fn from(
         PokemonCsv {
             name,
+            name_ja: _,
+            name_fr: _,
+            name_de: _,
+            name_es: _,
             pokedex_id,
             abilities: _,
             typing: _,
@@ -107,9 +274,10 @@ impl From<PokemonCsv> for PokemonTableRow {
             dark_attack_effectiveness,
             steel_attack_effectiveness,
             fairy_attack_effectiveness,
+            last_modified: _,
         }: PokemonCsv,
     ) -> Self {
-        let slug = name.to_kebab_case();
+        let slug = slugify("", &name);
         let id = PokemonId(Ksuid::new(None, None));
         PokemonTableRow {
             id,
@@ -157,10 +325,155 @@ impl From<PokemonCsv> for PokemonTableRow {
     }
 }
 
-pub async fn insert_pokemon(
-    pool: MySqlPool,
-    PokemonTableRow {
-        id,
+impl PokemonTableRow {
+    /// How effective an attack of each type is against this pokemon.
+    pub fn effectiveness_map(&self) -> BTreeMap<PokemonType, f32> {
+        BTreeMap::from([
+            (PokemonType::Normal, self.normal_attack_effectiveness),
+            (PokemonType::Fire, self.fire_attack_effectiveness),
+            (PokemonType::Water, self.water_attack_effectiveness),
+            (PokemonType::Electric, self.electric_attack_effectiveness),
+            (PokemonType::Grass, self.grass_attack_effectiveness),
+            (PokemonType::Ice, self.ice_attack_effectiveness),
+            (PokemonType::Fighting, self.fighting_attack_effectiveness),
+            (PokemonType::Poison, self.poison_attack_effectiveness),
+            (PokemonType::Ground, self.ground_attack_effectiveness),
+            (PokemonType::Flying, self.fly_attack_effectiveness),
+            (PokemonType::Psychic, self.psychic_attack_effectiveness),
+            (PokemonType::Bug, self.bug_attack_effectiveness),
+            (PokemonType::Rock, self.rock_attack_effectiveness),
+            (PokemonType::Ghost, self.ghost_attack_effectiveness),
+            (PokemonType::Dragon, self.dragon_attack_effectiveness),
+            (PokemonType::Dark, self.dark_attack_effectiveness),
+            (PokemonType::Steel, self.steel_attack_effectiveness),
+            (PokemonType::Fairy, self.fairy_attack_effectiveness),
+        ])
+    }
+}
+
+/// How the `f32` columns of a `PokemonTableRow`, and `Effectiveness`, are
+/// serialized inside `FloatFormat::scope`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// JSON numbers, `0.25` and `2`.
+    #[default]
+    Number,
+    /// Strings with three decimal places, `"0.250"` and `"2.000"`, for
+    /// clients that lose precision parsing numbers.
+    String,
+}
+
+tokio::task_local! {
+    static FLOAT_FORMAT: FloatFormat;
+}
+
+impl FloatFormat {
+    /// Runs `future` with every float serialized inside it written as `self`.
+    /// A task local rather than a thread local, so it follows the future
+    /// across threads.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        FLOAT_FORMAT.scope(self, future).await
+    }
+
+    fn current() -> Self {
+        FLOAT_FORMAT.try_with(|format| *format).unwrap_or_default()
+    }
+
+    fn fixed(value: f32) -> String {
+        format!("{value:.3}")
+    }
+}
+
+/// `serialize_with` for `f32` columns, honouring `FloatFormat::scope`.
+pub fn serialize_float<S>(value: &f32, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match FloatFormat::current() {
+        FloatFormat::Number => serializer.serialize_f32(*value),
+        FloatFormat::String => serializer.serialize_str(&FloatFormat::fixed(*value)),
+    }
+}
+
+/// `serialize_float` for nullable columns, `None` stays `null`.
+pub fn serialize_optional_float<S>(value: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => serialize_float(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// An attack effectiveness multiplier as it goes out in responses: rounded to
+/// three decimal places so `f32` noise like `0.25000001` never shows, and
+/// whole multipliers written as integers (`2` rather than `2.0`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Effectiveness(pub f32);
+
+impl Serialize for Effectiveness {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if FloatFormat::current() == FloatFormat::String {
+            return serializer.serialize_str(&FloatFormat::fixed(self.0));
+        }
+        let rounded = (f64::from(self.0) * 1000.0).round() / 1000.0;
+        if rounded.fract() == 0.0 {
+            serializer.serialize_i64(rounded as i64)
+        } else {
+            serializer.serialize_f64(rounded)
+        }
+    }
+}
+
+/// `serialize_with` for the `*_attack_effectiveness` columns.
+pub fn serialize_effectiveness<S>(value: &f32, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Effectiveness(*value).serialize(serializer)
+}
+
+/// What writing a row did to the `pokemon` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InsertAction {
+    Inserted,
+    Updated,
+    Unchanged,
+    /// The stored row is at least as new as the source, so it was left alone.
+    Skipped,
+}
+
+/// The `slug` column for a pokemon called `name`. A non-empty `prefix` keeps
+/// a second dataset loaded into the same table from colliding with the
+/// canonical slugs, since `slug` is `UNIQUE`.
+pub fn slugify(prefix: &str, name: &str) -> String {
+    format!("{prefix}{}", name.to_kebab_case())
+}
+
+/// Which row a write landed on, and what it did. For an existing pokemon the
+/// `id` is the stored one, not the id of the row that was passed in.
+#[derive(Debug, Clone, Serialize)]
+pub struct InsertOutcome {
+    pub slug: String,
+    pub id: PokemonId,
+    pub action: InsertAction,
+    /// The fields an update changed, empty unless `action` is `Updated`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<String>,
+}
+
+/// The fields of `row` that differ from the `stored` version of the same
+/// pokemon, compared as stored rather than as serialized. `id` is left out,
+/// since updates keep the stored one.
+pub fn changed_fields(stored: &PokemonTableRow, row: &PokemonTableRow) -> Vec<String> {
+    // destructured so a new column can't be left out of the comparison
+    let PokemonTableRow {
+        id: _,
         name,
         slug,
         pokedex_id,
@@ -201,58 +514,180 @@ pub async fn insert_pokemon(
         dark_attack_effectiveness,
         steel_attack_effectiveness,
         fairy_attack_effectiveness,
-    }: PokemonTableRow,
-) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
-    sqlx::query!(
-        r#"
-        INSERT INTO pokemon (
-            id,
-            slug,
-            name,
-            pokedex_id,
-            hp,
-            attack,
-            defense,
-            special_attack,
-            special_defense,
-            speed,
-            height,
-            weight,
-            generation,
-            female_rate,
-            genderless,
-            legendary_or_mythical,
-            is_default,
-            forms_switchable,
-            base_experience,
-            capture_rate,
-            base_happiness,
-            primary_color,
-            number_pokemon_with_typing,
-            normal_attack_effectiveness,
-            fire_attack_effectiveness,
-            water_attack_effectiveness,
-            electric_attack_effectiveness,
-            grass_attack_effectiveness,
-            ice_attack_effectiveness,
-            fighting_attack_effectiveness,
-            poison_attack_effectiveness,
-            ground_attack_effectiveness,
-            fly_attack_effectiveness,
-            psychic_attack_effectiveness,
-            bug_attack_effectiveness,
-            rock_attack_effectiveness,
-            ghost_attack_effectiveness,
-            dragon_attack_effectiveness,
-            dark_attack_effectiveness,
-            steel_attack_effectiveness,
-            fairy_attack_effectiveness
-         )
-        VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)
-        "#,
+    } = row;
+    [
+        ("name", *name != stored.name),
+        ("slug", *slug != stored.slug),
+        ("pokedex_id", *pokedex_id != stored.pokedex_id),
+        ("hp", *hp != stored.hp),
+        ("attack", *attack != stored.attack),
+        ("defense", *defense != stored.defense),
+        ("special_attack", *special_attack != stored.special_attack),
+        (
+            "special_defense",
+            *special_defense != stored.special_defense,
+        ),
+        ("speed", *speed != stored.speed),
+        ("height", *height != stored.height),
+        ("weight", *weight != stored.weight),
+        ("generation", *generation != stored.generation),
+        ("female_rate", *female_rate != stored.female_rate),
+        ("genderless", *genderless != stored.genderless),
+        (
+            "legendary_or_mythical",
+            *legendary_or_mythical != stored.legendary_or_mythical,
+        ),
+        ("is_default", *is_default != stored.is_default),
+        (
+            "forms_switchable",
+            *forms_switchable != stored.forms_switchable,
+        ),
+        (
+            "base_experience",
+            *base_experience != stored.base_experience,
+        ),
+        ("capture_rate", *capture_rate != stored.capture_rate),
+        ("base_happiness", *base_happiness != stored.base_happiness),
+        ("primary_color", *primary_color != stored.primary_color),
+        (
+            "number_pokemon_with_typing",
+            *number_pokemon_with_typing != stored.number_pokemon_with_typing,
+        ),
+        (
+            "normal_attack_effectiveness",
+            *normal_attack_effectiveness != stored.normal_attack_effectiveness,
+        ),
+        (
+            "fire_attack_effectiveness",
+            *fire_attack_effectiveness != stored.fire_attack_effectiveness,
+        ),
+        (
+            "water_attack_effectiveness",
+            *water_attack_effectiveness != stored.water_attack_effectiveness,
+        ),
+        (
+            "electric_attack_effectiveness",
+            *electric_attack_effectiveness != stored.electric_attack_effectiveness,
+        ),
+        (
+            "grass_attack_effectiveness",
+            *grass_attack_effectiveness != stored.grass_attack_effectiveness,
+        ),
+        (
+            "ice_attack_effectiveness",
+            *ice_attack_effectiveness != stored.ice_attack_effectiveness,
+        ),
+        (
+            "fighting_attack_effectiveness",
+            *fighting_attack_effectiveness != stored.fighting_attack_effectiveness,
+        ),
+        (
+            "poison_attack_effectiveness",
+            *poison_attack_effectiveness != stored.poison_attack_effectiveness,
+        ),
+        (
+            "ground_attack_effectiveness",
+            *ground_attack_effectiveness != stored.ground_attack_effectiveness,
+        ),
+        (
+            "fly_attack_effectiveness",
+            *fly_attack_effectiveness != stored.fly_attack_effectiveness,
+        ),
+        (
+            "psychic_attack_effectiveness",
+            *psychic_attack_effectiveness != stored.psychic_attack_effectiveness,
+        ),
+        (
+            "bug_attack_effectiveness",
+            *bug_attack_effectiveness != stored.bug_attack_effectiveness,
+        ),
+        (
+            "rock_attack_effectiveness",
+            *rock_attack_effectiveness != stored.rock_attack_effectiveness,
+        ),
+        (
+            "ghost_attack_effectiveness",
+            *ghost_attack_effectiveness != stored.ghost_attack_effectiveness,
+        ),
+        (
+            "dragon_attack_effectiveness",
+            *dragon_attack_effectiveness != stored.dragon_attack_effectiveness,
+        ),
+        (
+            "dark_attack_effectiveness",
+            *dark_attack_effectiveness != stored.dark_attack_effectiveness,
+        ),
+        (
+            "steel_attack_effectiveness",
+            *steel_attack_effectiveness != stored.steel_attack_effectiveness,
+        ),
+        (
+            "fairy_attack_effectiveness",
+            *fairy_attack_effectiveness != stored.fairy_attack_effectiveness,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field.to_string())
+    .collect()
+}
+
+/// Writes `row` on its own, committing straight away, see `insert_pokemon_tx`
+/// for what happens when its slug is already stored. Use `insert_pokemon_tx`
+/// to make the write part of a larger transaction.
+pub async fn insert_pokemon(
+    pool: MySqlPool,
+    table: PokemonTable,
+    row: PokemonTableRow,
+) -> Result<InsertOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let outcome = insert_pokemon_tx(&mut tx, table, row).await?;
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+/// Writes `row` inside `tx`. Nothing is committed; the caller decides whether
+/// the write sticks.
+///
+/// When no pokemon has `row.slug` yet, `row` is inserted as it is. Otherwise
+/// the stored row is locked and compared field by field with `row`: if
+/// nothing differs it's left alone and reported `Unchanged`, and if anything
+/// does every column but `id` is overwritten and it's reported `Updated`.
+/// Either way the outcome carries the stored `id`, which child rows must
+/// point at. A new slug whose `id` is already taken fails with a duplicate
+/// key error.
+pub async fn insert_pokemon_tx<'t>(
+    tx: &mut Transaction<'t, MySql>,
+    table: PokemonTable,
+    row: PokemonTableRow,
+) -> Result<InsertOutcome, sqlx::Error> {
+    match stored_pokemon_tx(tx, table, &row.slug).await? {
+        Some(stored) => update_pokemon_tx(tx, table, &stored, row).await,
+        None => insert_new_pokemon_tx(tx, table, row).await,
+    }
+}
+
+/// The stored pokemon with this slug, locked until `tx` ends.
+async fn stored_pokemon_tx<'t>(
+    tx: &mut Transaction<'t, MySql>,
+    table: PokemonTable,
+    slug: &str,
+) -> Result<Option<PokemonTableRow>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {POKEMON_COLUMNS} FROM {table} WHERE slug = ? FOR UPDATE"
+    ))
+    .bind(slug)
+    .fetch_optional(&mut **tx)
+    .await
+}
+
+async fn insert_new_pokemon_tx<'t>(
+    tx: &mut Transaction<'t, MySql>,
+    table: PokemonTable,
+    PokemonTableRow {
         id,
-        slug,
         name,
+        slug,
         pokedex_id,
         hp,
         attack,
@@ -291,15 +726,629 @@ pub async fn insert_pokemon(
         dark_attack_effectiveness,
         steel_attack_effectiveness,
         fairy_attack_effectiveness,
-    )
-    .execute(&pool)
-    .await
+    }: PokemonTableRow,
+) -> Result<InsertOutcome, sqlx::Error> {
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query!(
+                r#"
+                INSERT INTO pokemon (
+                    id,
+                    slug,
+                    name,
+                    pokedex_id,
+                    hp,
+                    attack,
+                    defense,
+                    special_attack,
+                    special_defense,
+                    speed,
+                    height,
+                    weight,
+                    generation,
+                    female_rate,
+                    genderless,
+                    legendary_or_mythical,
+                    is_default,
+                    forms_switchable,
+                    base_experience,
+                    capture_rate,
+                    base_happiness,
+                    primary_color,
+                    number_pokemon_with_typing,
+                    normal_attack_effectiveness,
+                    fire_attack_effectiveness,
+                    water_attack_effectiveness,
+                    electric_attack_effectiveness,
+                    grass_attack_effectiveness,
+                    ice_attack_effectiveness,
+                    fighting_attack_effectiveness,
+                    poison_attack_effectiveness,
+                    ground_attack_effectiveness,
+                    fly_attack_effectiveness,
+                    psychic_attack_effectiveness,
+                    bug_attack_effectiveness,
+                    rock_attack_effectiveness,
+                    ghost_attack_effectiveness,
+                    dragon_attack_effectiveness,
+                    dark_attack_effectiveness,
+                    steel_attack_effectiveness,
+                    fairy_attack_effectiveness
+                 )
+                VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)
+                "#,
+                id,
+                slug,
+                name,
+                pokedex_id,
+                hp,
+                attack,
+                defense,
+                special_attack,
+                special_defense,
+                speed,
+                height,
+                weight,
+                generation,
+                female_rate,
+                genderless,
+                legendary_or_mythical,
+                is_default,
+                forms_switchable,
+                base_experience,
+                capture_rate,
+                base_happiness,
+                primary_color,
+                number_pokemon_with_typing,
+                normal_attack_effectiveness,
+                fire_attack_effectiveness,
+                water_attack_effectiveness,
+                electric_attack_effectiveness,
+                grass_attack_effectiveness,
+                ice_attack_effectiveness,
+                fighting_attack_effectiveness,
+                poison_attack_effectiveness,
+                ground_attack_effectiveness,
+                fly_attack_effectiveness,
+                psychic_attack_effectiveness,
+                bug_attack_effectiveness,
+                rock_attack_effectiveness,
+                ghost_attack_effectiveness,
+                dragon_attack_effectiveness,
+                dark_attack_effectiveness,
+                steel_attack_effectiveness,
+                fairy_attack_effectiveness,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query!(
+                r#"
+                INSERT INTO pokemon_staging (
+                    id,
+                    slug,
+                    name,
+                    pokedex_id,
+                    hp,
+                    attack,
+                    defense,
+                    special_attack,
+                    special_defense,
+                    speed,
+                    height,
+                    weight,
+                    generation,
+                    female_rate,
+                    genderless,
+                    legendary_or_mythical,
+                    is_default,
+                    forms_switchable,
+                    base_experience,
+                    capture_rate,
+                    base_happiness,
+                    primary_color,
+                    number_pokemon_with_typing,
+                    normal_attack_effectiveness,
+                    fire_attack_effectiveness,
+                    water_attack_effectiveness,
+                    electric_attack_effectiveness,
+                    grass_attack_effectiveness,
+                    ice_attack_effectiveness,
+                    fighting_attack_effectiveness,
+                    poison_attack_effectiveness,
+                    ground_attack_effectiveness,
+                    fly_attack_effectiveness,
+                    psychic_attack_effectiveness,
+                    bug_attack_effectiveness,
+                    rock_attack_effectiveness,
+                    ghost_attack_effectiveness,
+                    dragon_attack_effectiveness,
+                    dark_attack_effectiveness,
+                    steel_attack_effectiveness,
+                    fairy_attack_effectiveness
+                 )
+                VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)
+                "#,
+                id,
+                slug,
+                name,
+                pokedex_id,
+                hp,
+                attack,
+                defense,
+                special_attack,
+                special_defense,
+                speed,
+                height,
+                weight,
+                generation,
+                female_rate,
+                genderless,
+                legendary_or_mythical,
+                is_default,
+                forms_switchable,
+                base_experience,
+                capture_rate,
+                base_happiness,
+                primary_color,
+                number_pokemon_with_typing,
+                normal_attack_effectiveness,
+                fire_attack_effectiveness,
+                water_attack_effectiveness,
+                electric_attack_effectiveness,
+                grass_attack_effectiveness,
+                ice_attack_effectiveness,
+                fighting_attack_effectiveness,
+                poison_attack_effectiveness,
+                ground_attack_effectiveness,
+                fly_attack_effectiveness,
+                psychic_attack_effectiveness,
+                bug_attack_effectiveness,
+                rock_attack_effectiveness,
+                ghost_attack_effectiveness,
+                dragon_attack_effectiveness,
+                dark_attack_effectiveness,
+                steel_attack_effectiveness,
+                fairy_attack_effectiveness,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+    };
+
+    Ok(InsertOutcome {
+        slug,
+        id,
+        action: InsertAction::Inserted,
+        changed: Vec::new(),
+    })
+}
+
+/// A pokemon's name in each language other than English, `None` where the
+/// dataset doesn't have one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalizedNames {
+    pub ja: Option<String>,
+    pub fr: Option<String>,
+    pub de: Option<String>,
+    pub es: Option<String>,
+}
+
+/// Stores the localized names of the pokemon with this slug, which must
+/// already be inserted.
+pub async fn set_localized_names(
+    pool: &MySqlPool,
+    table: PokemonTable,
+    slug: &str,
+    names: &LocalizedNames,
+) -> Result<(), sqlx::Error> {
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query!(
+                "UPDATE pokemon SET name_ja = ?, name_fr = ?, name_de = ?, name_es = ? WHERE slug = ?",
+                names.ja,
+                names.fr,
+                names.de,
+                names.es,
+                slug,
+            )
+            .execute(pool)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query!(
+                "UPDATE pokemon_staging SET name_ja = ?, name_fr = ?, name_de = ?, name_es = ? WHERE slug = ?",
+                names.ja,
+                names.fr,
+                names.de,
+                names.es,
+                slug,
+            )
+            .execute(pool)
+            .await?
+        }
+    };
+    Ok(())
+}
+
+/// Replaces the abilities, egg groups and types of the pokemon with `id`, in
+/// one transaction, so a reload leaves exactly the dataset's rows rather than
+/// adding a second copy.
+pub async fn replace_child_rows(
+    pool: &MySqlPool,
+    id: &PokemonId,
+    abilities: &[String],
+    egg_groups: &[String],
+    typing: &[PokemonType],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM abilities WHERE pokemon_id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+    for ability in abilities {
+        sqlx::query!(
+            "INSERT INTO abilities (id, pokemon_id, ability) VALUES (?, ?, ?)",
+            PokemonId::new(),
+            id,
+            ability,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query!("DELETE FROM egg_groups WHERE pokemon_id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+    for egg_group in egg_groups {
+        sqlx::query!(
+            "INSERT INTO egg_groups (id, pokemon_id, egg_group) VALUES (?, ?, ?)",
+            PokemonId::new(),
+            id,
+            egg_group,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query!("DELETE FROM typing WHERE pokemon_id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+    for typing in typing {
+        sqlx::query!(
+            "INSERT INTO typing (id, pokemon_id, typing) VALUES (?, ?, ?)",
+            PokemonId::new(),
+            id,
+            typing,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Replaces what the pokemon with `id` evolves from, `None` for a pokemon
+/// that doesn't evolve from anything.
+pub async fn replace_evolution(
+    pool: &MySqlPool,
+    id: &PokemonId,
+    evolves_from: Option<&PokemonId>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM evolutions WHERE pokemon_id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+    if let Some(evolves_from) = evolves_from {
+        sqlx::query!(
+            "INSERT INTO evolutions (id, pokemon_id, evolves_from) VALUES (?, ?, ?)",
+            PokemonId::new(),
+            id,
+            evolves_from,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Whether a source row last modified on `source_updated_at` should replace
+/// a stored row whose `updated_at` is `stored_updated_at` (unix seconds).
+///
+/// The source wins only when its date is strictly after the stored
+/// timestamp, read as midnight UTC. A hand correction made after the dataset
+/// was exported is therefore kept, and so is a row loaded earlier the same
+/// day from the same dataset.
+fn source_is_newer(source_updated_at: NaiveDate, stored_updated_at: i64) -> bool {
+    let source_updated_at = source_updated_at
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .timestamp();
+    source_updated_at > stored_updated_at
+}
+
+/// Like `insert_pokemon`, but an existing pokemon with the same slug is only
+/// overwritten when `source_updated_at` is newer, see `source_is_newer`.
+///
+/// MySQL's `ON DUPLICATE KEY UPDATE` can't take a `WHERE`, and there's no
+/// `RETURNING` to say what an update changed, so the stored row is read and
+/// locked first, compared with `row`, and only then updated by slug, all in
+/// one transaction.
+pub async fn upsert_pokemon_if_newer(
+    pool: MySqlPool,
+    table: PokemonTable,
+    row: PokemonTableRow,
+    source_updated_at: NaiveDate,
+) -> Result<InsertOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let outcome = match stored_pokemon_tx(&mut tx, table, &row.slug).await? {
+        None => insert_new_pokemon_tx(&mut tx, table, row).await?,
+        Some(stored) => {
+            // already locked by `stored_pokemon_tx`
+            let stored_updated_at: i64 = sqlx::query_scalar(&format!(
+                "SELECT CAST(UNIX_TIMESTAMP(updated_at) AS SIGNED) FROM {table} WHERE slug = ?"
+            ))
+            .bind(&row.slug)
+            .fetch_one(&mut *tx)
+            .await?;
+            if source_is_newer(source_updated_at, stored_updated_at) {
+                update_pokemon_tx(&mut tx, table, &stored, row).await?
+            } else {
+                InsertOutcome {
+                    slug: row.slug,
+                    id: stored.id,
+                    action: InsertAction::Skipped,
+                    changed: Vec::new(),
+                }
+            }
+        }
+    };
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+/// Overwrites every column of `stored` but `id` with `row`, or leaves it
+/// alone when no field differs. `stored` must be locked by `tx`.
+async fn update_pokemon_tx<'t>(
+    tx: &mut Transaction<'t, MySql>,
+    table: PokemonTable,
+    stored: &PokemonTableRow,
+    row: PokemonTableRow,
+) -> Result<InsertOutcome, sqlx::Error> {
+    let changed = changed_fields(stored, &row);
+    if changed.is_empty() {
+        return Ok(InsertOutcome {
+            slug: row.slug,
+            id: stored.id.clone(),
+            action: InsertAction::Unchanged,
+            changed,
+        });
+    }
+
+    match table {
+        PokemonTable::Pokemon => {
+            sqlx::query!(
+                r#"
+                UPDATE pokemon SET
+                    name = ?,
+                    pokedex_id = ?,
+                    hp = ?,
+                    attack = ?,
+                    defense = ?,
+                    special_attack = ?,
+                    special_defense = ?,
+                    speed = ?,
+                    height = ?,
+                    weight = ?,
+                    generation = ?,
+                    female_rate = ?,
+                    genderless = ?,
+                    legendary_or_mythical = ?,
+                    is_default = ?,
+                    forms_switchable = ?,
+                    base_experience = ?,
+                    capture_rate = ?,
+                    base_happiness = ?,
+                    primary_color = ?,
+                    number_pokemon_with_typing = ?,
+                    normal_attack_effectiveness = ?,
+                    fire_attack_effectiveness = ?,
+                    water_attack_effectiveness = ?,
+                    electric_attack_effectiveness = ?,
+                    grass_attack_effectiveness = ?,
+                    ice_attack_effectiveness = ?,
+                    fighting_attack_effectiveness = ?,
+                    poison_attack_effectiveness = ?,
+                    ground_attack_effectiveness = ?,
+                    fly_attack_effectiveness = ?,
+                    psychic_attack_effectiveness = ?,
+                    bug_attack_effectiveness = ?,
+                    rock_attack_effectiveness = ?,
+                    ghost_attack_effectiveness = ?,
+                    dragon_attack_effectiveness = ?,
+                    dark_attack_effectiveness = ?,
+                    steel_attack_effectiveness = ?,
+                    fairy_attack_effectiveness = ?
+                WHERE slug = ?
+                "#,
+                row.name,
+                row.pokedex_id,
+                row.hp,
+                row.attack,
+                row.defense,
+                row.special_attack,
+                row.special_defense,
+                row.speed,
+                row.height,
+                row.weight,
+                row.generation,
+                row.female_rate,
+                row.genderless,
+                row.legendary_or_mythical,
+                row.is_default,
+                row.forms_switchable,
+                row.base_experience,
+                row.capture_rate,
+                row.base_happiness,
+                row.primary_color,
+                row.number_pokemon_with_typing,
+                row.normal_attack_effectiveness,
+                row.fire_attack_effectiveness,
+                row.water_attack_effectiveness,
+                row.electric_attack_effectiveness,
+                row.grass_attack_effectiveness,
+                row.ice_attack_effectiveness,
+                row.fighting_attack_effectiveness,
+                row.poison_attack_effectiveness,
+                row.ground_attack_effectiveness,
+                row.fly_attack_effectiveness,
+                row.psychic_attack_effectiveness,
+                row.bug_attack_effectiveness,
+                row.rock_attack_effectiveness,
+                row.ghost_attack_effectiveness,
+                row.dragon_attack_effectiveness,
+                row.dark_attack_effectiveness,
+                row.steel_attack_effectiveness,
+                row.fairy_attack_effectiveness,
+                row.slug,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        PokemonTable::Staging => {
+            sqlx::query!(
+                r#"
+                UPDATE pokemon_staging SET
+                    name = ?,
+                    pokedex_id = ?,
+                    hp = ?,
+                    attack = ?,
+                    defense = ?,
+                    special_attack = ?,
+                    special_defense = ?,
+                    speed = ?,
+                    height = ?,
+                    weight = ?,
+                    generation = ?,
+                    female_rate = ?,
+                    genderless = ?,
+                    legendary_or_mythical = ?,
+                    is_default = ?,
+                    forms_switchable = ?,
+                    base_experience = ?,
+                    capture_rate = ?,
+                    base_happiness = ?,
+                    primary_color = ?,
+                    number_pokemon_with_typing = ?,
+                    normal_attack_effectiveness = ?,
+                    fire_attack_effectiveness = ?,
+                    water_attack_effectiveness = ?,
+                    electric_attack_effectiveness = ?,
+                    grass_attack_effectiveness = ?,
+                    ice_attack_effectiveness = ?,
+                    fighting_attack_effectiveness = ?,
+                    poison_attack_effectiveness = ?,
+                    ground_attack_effectiveness = ?,
+                    fly_attack_effectiveness = ?,
+                    psychic_attack_effectiveness = ?,
+                    bug_attack_effectiveness = ?,
+                    rock_attack_effectiveness = ?,
+                    ghost_attack_effectiveness = ?,
+                    dragon_attack_effectiveness = ?,
+                    dark_attack_effectiveness = ?,
+                    steel_attack_effectiveness = ?,
+                    fairy_attack_effectiveness = ?
+                WHERE slug = ?
+                "#,
+                row.name,
+                row.pokedex_id,
+                row.hp,
+                row.attack,
+                row.defense,
+                row.special_attack,
+                row.special_defense,
+                row.speed,
+                row.height,
+                row.weight,
+                row.generation,
+                row.female_rate,
+                row.genderless,
+                row.legendary_or_mythical,
+                row.is_default,
+                row.forms_switchable,
+                row.base_experience,
+                row.capture_rate,
+                row.base_happiness,
+                row.primary_color,
+                row.number_pokemon_with_typing,
+                row.normal_attack_effectiveness,
+                row.fire_attack_effectiveness,
+                row.water_attack_effectiveness,
+                row.electric_attack_effectiveness,
+                row.grass_attack_effectiveness,
+                row.ice_attack_effectiveness,
+                row.fighting_attack_effectiveness,
+                row.poison_attack_effectiveness,
+                row.ground_attack_effectiveness,
+                row.fly_attack_effectiveness,
+                row.psychic_attack_effectiveness,
+                row.bug_attack_effectiveness,
+                row.rock_attack_effectiveness,
+                row.ghost_attack_effectiveness,
+                row.dragon_attack_effectiveness,
+                row.dark_attack_effectiveness,
+                row.steel_attack_effectiveness,
+                row.fairy_attack_effectiveness,
+                row.slug,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+    };
+
+    Ok(InsertOutcome {
+        slug: row.slug,
+        id: stored.id.clone(),
+        action: InsertAction::Updated,
+        changed,
+    })
 }
 
+/// Unix time, in seconds, that KSUID timestamps count from.
+const KSUID_EPOCH: i64 = 1_400_000_000;
+
 impl PokemonId {
     pub fn new() -> Self {
         Self(Ksuid::new(None, None))
     }
+
+    /// The id with this `timestamp` (unix seconds) and `payload`, so a
+    /// re-import that derives both from the same pokemon, e.g. its pokedex id
+    /// and a fixed date, gets the same id every time.
+    ///
+    /// `new` is unique by construction, this is only as unique as the parts
+    /// it is given. Two pokemon built from the same parts share an id, and
+    /// the second insert overwrites the first.
+    pub fn from_parts(timestamp: i64, payload: [u8; 16]) -> Result<Self, PokemonIdError> {
+        let timestamp = timestamp
+            .checked_sub(KSUID_EPOCH)
+            .and_then(|seconds| u32::try_from(seconds).ok())
+            .ok_or(PokemonIdError::TimestampOutOfRange)?;
+        let mut bytes = [0; 20];
+        bytes[..4].copy_from_slice(&timestamp.to_be_bytes());
+        bytes[4..].copy_from_slice(&payload);
+        Ok(PokemonId(Ksuid::from_bytes(bytes)))
+    }
+
+    /// The smallest id that can be created at `timestamp` (unix seconds).
+    /// Ids sort by creation time, bytes and base62 alike, so
+    /// `WHERE id >= ? AND id <= ?` with `min_for(from)` and `max_for(to)`
+    /// finds everything created in `from..=to` off the primary key index.
+    pub fn min_for(timestamp: i64) -> Result<Self, PokemonIdError> {
+        Self::from_parts(timestamp, [0; 16])
+    }
+
+    /// The largest id that can be created at `timestamp`, see `min_for`.
+    pub fn max_for(timestamp: i64) -> Result<Self, PokemonIdError> {
+        Self::from_parts(timestamp, [u8::MAX; 16])
+    }
 }
 
 impl<'q> Encode<'q, MySql> for PokemonId {
@@ -314,9 +1363,64 @@ impl<'r> Decode<'r, MySql> for PokemonId {
         value: <MySql as HasValueRef<'r>>::ValueRef,
     ) -> Result<PokemonId, Box<dyn std::error::Error + 'static + Send + Sync>> {
         let value = <&[u8] as Decode<MySql>>::decode(value)?;
-        let base62_ksuid = std::str::from_utf8(&value)?;
-        let ksuid = Ksuid::from_base62(&base62_ksuid)?;
-        Ok(PokemonId(ksuid))
+        let base62_ksuid = std::str::from_utf8(value).map_err(|_| PokemonIdError::InvalidUtf8)?;
+        Ok(base62_ksuid.parse()?)
+    }
+}
+
+/// Why a value could not be turned into a `PokemonId`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PokemonIdError {
+    InvalidUtf8,
+    InvalidBase62 { len: usize },
+    WrongLength,
+    TimestampOutOfRange,
+}
+
+impl fmt::Display for PokemonIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PokemonIdError::InvalidUtf8 => write!(
+                f,
+                "pokemon id is not valid utf-8, expected a 27 character base62 ksuid"
+            ),
+            PokemonIdError::InvalidBase62 { len } => write!(
+                f,
+                "pokemon id is not a valid base62 ksuid, \
+                expected 27 characters of [0-9A-Za-z] but got {len}"
+            ),
+            PokemonIdError::WrongLength => {
+                write!(f, "pokemon id in binary form must be exactly 20 bytes")
+            }
+            PokemonIdError::TimestampOutOfRange => write!(
+                f,
+                "pokemon id timestamps must be from 2014-05-13 to 2150-06-19"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PokemonIdError {}
+
+/// Parses the base62 form, e.g. `0ujtsYcgvSTl8PAuAdqWYSMnLOv`.
+impl FromStr for PokemonId {
+    type Err = PokemonIdError;
+
+    fn from_str(base62_ksuid: &str) -> Result<Self, Self::Err> {
+        Ksuid::from_base62(base62_ksuid)
+            .map(PokemonId)
+            .map_err(|_| PokemonIdError::InvalidBase62 {
+                len: base62_ksuid.len(),
+            })
+    }
+}
+
+impl TryFrom<&[u8]> for PokemonId {
+    type Error = PokemonIdError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; 20] = bytes.try_into().map_err(|_| PokemonIdError::WrongLength)?;
+        Ok(PokemonId(Ksuid::from_bytes(bytes)))
     }
 }
 
@@ -329,12 +1433,364 @@ impl Type<MySql> for PokemonId {
     }
 }
 
+/// Human-readable formats (JSON) get the base62 string, binary formats get
+/// the 20 raw bytes.
 impl Serialize for PokemonId {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let id = self.0.to_base62();
-        serializer.serialize_str(&id)
+        if serializer.is_human_readable() {
+            let id = self.0.to_base62();
+            serializer.serialize_str(&id)
+        } else {
+            serializer.serialize_bytes(self.0.bytes())
+        }
+    }
+}
+
+/// The mirror of `Serialize`: base62 strings from human-readable formats,
+/// raw bytes from binary ones.
+impl<'de> Deserialize<'de> for PokemonId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PokemonIdVisitor;
+
+        impl<'de> de::Visitor<'de> for PokemonIdVisitor {
+            type Value = PokemonId;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a base62 ksuid string or 20 ksuid bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<PokemonId, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<PokemonId, E> {
+                PokemonId::try_from(value).map_err(E::custom)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PokemonIdVisitor)
+        } else {
+            deserializer.deserialize_bytes(PokemonIdVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::db_test;
+    use serde_test::{assert_ser_tokens, Configure, Token};
+
+    const BASE62: &str = "0ujtsYcgvSTl8PAuAdqWYSMnLOv";
+    const BYTES: [u8; 20] = [
+        6, 105, 247, 239, 181, 161, 205, 52, 181, 249, 157, 17, 84, 251, 104, 83, 52, 92, 151, 53,
+    ];
+
+    fn known_id() -> PokemonId {
+        PokemonId(Ksuid::from_base62(BASE62).unwrap())
+    }
+
+    fn bulbasaur() -> PokemonTableRow {
+        csv::Reader::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/pokemon.csv"))
+            .unwrap()
+            .deserialize::<PokemonCsv>()
+            .map(Result::unwrap)
+            .find(|pokemon| pokemon.name == "Bulbasaur")
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn only_strictly_newer_sources_overwrite() {
+        let exported = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        // 2023-06-30T12:00:00Z, a hand edit before the export
+        assert!(source_is_newer(exported, 1_688_126_400));
+        // 2023-07-02T09:00:00Z, a hand edit after the export
+        assert!(!source_is_newer(exported, 1_688_288_400));
+        // loaded at exactly midnight on the export date
+        assert!(!source_is_newer(exported, 1_688_169_600));
+    }
+
+    #[test]
+    fn only_known_tables_parse() {
+        assert_eq!(PokemonTable::parse("pokemon"), Some(PokemonTable::Pokemon));
+        assert_eq!(
+            PokemonTable::parse("pokemon_staging"),
+            Some(PokemonTable::Staging)
+        );
+        assert_eq!(PokemonTable::parse("pokemon; DROP TABLE pokemon"), None);
+        assert_eq!(PokemonTable::parse("Pokemon"), None);
+    }
+
+    db_test! {
+        async fn staged_pokemon_stay_out_of_the_served_table(pool: MySqlPool) {
+            let staged = insert_pokemon(pool.clone(), PokemonTable::Staging, bulbasaur())
+                .await
+                .unwrap();
+            assert_eq!(staged.action, InsertAction::Inserted);
+            let again = insert_pokemon(pool.clone(), PokemonTable::Staging, bulbasaur())
+                .await
+                .unwrap();
+            assert_eq!(again.action, InsertAction::Unchanged);
+
+            let pool = &pool;
+            let count = |table: PokemonTable| async move {
+                sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table}"))
+                    .fetch_one(pool)
+                    .await
+            };
+            assert_eq!(count(PokemonTable::Staging).await.unwrap(), 1);
+            assert_eq!(count(PokemonTable::Pokemon).await.unwrap(), 0);
+        }
+    }
+
+    db_test! {
+        async fn transactional_inserts_are_left_to_the_caller(pool: MySqlPool) {
+            let row = || PokemonTableRow {
+                name: "Pikachu".to_string(),
+                slug: "pikachu".to_string(),
+                ..Default::default()
+            };
+            let count = || sqlx::query_scalar!("SELECT COUNT(*) FROM pokemon").fetch_one(&pool);
+
+            let mut tx = pool.begin().await.unwrap();
+            insert_pokemon_tx(&mut tx, PokemonTable::Pokemon, row())
+                .await
+                .unwrap();
+            tx.rollback().await.unwrap();
+            assert_eq!(count().await.unwrap(), 0);
+
+            let mut tx = pool.begin().await.unwrap();
+            let outcome = insert_pokemon_tx(&mut tx, PokemonTable::Pokemon, row())
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+            assert_eq!(outcome.action, InsertAction::Inserted);
+            assert_eq!(count().await.unwrap(), 1);
+        }
+    }
+
+    db_test! {
+        async fn rewriting_a_stored_slug_reports_what_happened(pool: MySqlPool) {
+            let stored = insert_pokemon(pool.clone(), PokemonTable::Pokemon, bulbasaur())
+                .await
+                .unwrap();
+            assert_eq!(stored.action, InsertAction::Inserted);
+
+            let again = insert_pokemon(pool.clone(), PokemonTable::Pokemon, bulbasaur())
+                .await
+                .unwrap();
+            assert_eq!(again.action, InsertAction::Unchanged);
+            assert_eq!(again.id, stored.id);
+
+            let edited = insert_pokemon(
+                pool.clone(),
+                PokemonTable::Pokemon,
+                PokemonTableRow {
+                    hp: Stat(50),
+                    ..bulbasaur()
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(edited.action, InsertAction::Updated);
+            assert_eq!(edited.id, stored.id);
+            assert_eq!(edited.changed, ["hp"]);
+        }
+    }
+
+    db_test! {
+        async fn reloading_child_rows_replaces_them(pool: MySqlPool) {
+            let bulbasaur = insert_pokemon(pool.clone(), PokemonTable::Pokemon, bulbasaur())
+                .await
+                .unwrap();
+            let abilities = ["Overgrow".to_string(), "Chlorophyll".to_string()];
+            for _ in 0..2 {
+                replace_child_rows(
+                    &pool,
+                    &bulbasaur.id,
+                    &abilities,
+                    &["Monster".to_string()],
+                    &[PokemonType::Grass, PokemonType::Poison],
+                )
+                .await
+                .unwrap();
+            }
+            replace_child_rows(&pool, &bulbasaur.id, &abilities[..1], &[], &[])
+                .await
+                .unwrap();
+
+            let count: i64 = sqlx::query_scalar(
+                "SELECT (SELECT COUNT(*) FROM abilities) + (SELECT COUNT(*) FROM egg_groups) \
+                + (SELECT COUNT(*) FROM typing)",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(count, 1);
+        }
+    }
+
+    #[test]
+    fn changed_fields_ignore_the_id() {
+        let stored = bulbasaur();
+        let mut row = PokemonTableRow {
+            id: PokemonId::new(),
+            ..stored.clone()
+        };
+        assert!(changed_fields(&stored, &row).is_empty());
+
+        row.hp = Stat(50);
+        row.primary_color = "teal".to_string();
+        assert_eq!(changed_fields(&stored, &row), ["hp", "primary_color"]);
+
+        // finer than the three decimals effectiveness is served with
+        let row = PokemonTableRow {
+            fire_attack_effectiveness: stored.fire_attack_effectiveness + 0.0001,
+            ..stored.clone()
+        };
+        assert_eq!(changed_fields(&stored, &row), ["fire_attack_effectiveness"]);
+    }
+
+    #[test]
+    fn default_rows_are_neutral_to_every_type() {
+        let row = PokemonTableRow {
+            slug: "missingno".to_string(),
+            hp: Stat(33),
+            ..Default::default()
+        };
+
+        assert_eq!(row.hp, Stat(33));
+        assert_eq!(serde_json::to_value(row.hp).unwrap(), 33);
+        assert!(row
+            .effectiveness_map()
+            .values()
+            .all(|&effectiveness| effectiveness == 1.0));
+        assert_ne!(row.id, PokemonTableRow::default().id);
+    }
+
+    #[test]
+    fn effectiveness_is_rounded_and_whole_values_are_integers() {
+        let rendered = |value: f32| serde_json::to_string(&Effectiveness(value)).unwrap();
+
+        assert_eq!(rendered(0.0), "0");
+        assert_eq!(rendered(0.25), "0.25");
+        assert_eq!(rendered(0.5), "0.5");
+        assert_eq!(rendered(1.0), "1");
+        assert_eq!(rendered(2.0), "2");
+        assert_eq!(rendered(4.0), "4");
+        assert_eq!(rendered(0.250_000_01), "0.25");
+        assert_eq!(rendered(1.0 / 3.0), "0.333");
+    }
+
+    #[tokio::test]
+    async fn floats_are_strings_only_inside_a_string_scope() {
+        let bulbasaur = bulbasaur();
+        let json = || serde_json::to_value(&bulbasaur).unwrap();
+
+        let numbers = json();
+        assert_eq!(numbers["female_rate"], 0.125);
+        assert_eq!(numbers["fire_attack_effectiveness"], 2);
+
+        let strings = FloatFormat::String.scope(async { json() }).await;
+        assert_eq!(strings["female_rate"], "0.125");
+        assert_eq!(strings["fire_attack_effectiveness"], "2.000");
+        assert_eq!(strings["number_pokemon_with_typing"], "15.000");
+        assert_eq!(strings["hp"], 45);
+        assert_eq!(json(), numbers);
+    }
+
+    #[test]
+    fn slugs_are_kebab_case_with_an_optional_prefix() {
+        assert_eq!(slugify("", "Ho Oh"), "ho-oh");
+        assert_eq!(slugify("fan-", "Mr Mime"), "fan-mr-mime");
+    }
+
+    #[test]
+    fn effectiveness_map_covers_every_type() {
+        let effectiveness = bulbasaur().effectiveness_map();
+
+        assert_eq!(effectiveness.len(), PokemonType::ALL.len());
+        assert_eq!(effectiveness[&PokemonType::Fire], 2.0);
+        assert_eq!(effectiveness[&PokemonType::Water], 0.5);
+        assert_eq!(effectiveness[&PokemonType::Grass], 0.25);
+        assert_eq!(effectiveness[&PokemonType::Flying], 2.0);
+        assert_eq!(effectiveness[&PokemonType::Poison], 1.0);
+        assert_eq!(effectiveness[&PokemonType::Fairy], 0.5);
+    }
+
+    #[test]
+    fn parses_and_rejects_pokemon_ids() {
+        assert_eq!(BASE62.parse::<PokemonId>().unwrap().0.to_base62(), BASE62);
+        assert_eq!(
+            "not-a-ksuid".parse::<PokemonId>().unwrap_err(),
+            PokemonIdError::InvalidBase62 { len: 11 }
+        );
+        assert_eq!(
+            PokemonId::try_from(&BYTES[..19]).unwrap_err(),
+            PokemonIdError::WrongLength
+        );
+    }
+
+    #[test]
+    fn ids_built_from_the_same_parts_are_equal() {
+        let payload: [u8; 16] = BYTES[4..].try_into().unwrap();
+        assert_eq!(
+            PokemonId::from_parts(1_507_608_047, payload).unwrap(),
+            known_id()
+        );
+        assert_eq!(
+            PokemonId::from_parts(KSUID_EPOCH, [7; 16]),
+            PokemonId::from_parts(KSUID_EPOCH, [7; 16])
+        );
+        assert_eq!(
+            PokemonId::from_parts(KSUID_EPOCH - 1, payload).unwrap_err(),
+            PokemonIdError::TimestampOutOfRange
+        );
+    }
+
+    #[test]
+    fn boundary_ids_bracket_ids_from_their_second() {
+        let base62 = |id: PokemonId| id.0.to_base62();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let created = base62(PokemonId::new());
+        assert!(base62(PokemonId::min_for(now - 1).unwrap()) <= created);
+        assert!(created <= base62(PokemonId::max_for(now + 1).unwrap()));
+
+        let payload: [u8; 16] = BYTES[4..].try_into().unwrap();
+        let id = base62(PokemonId::from_parts(1_507_608_047, payload).unwrap());
+        assert!(base62(PokemonId::max_for(1_507_608_046).unwrap()) < id);
+        assert!(base62(PokemonId::min_for(1_507_608_047).unwrap()) <= id);
+        assert!(id <= base62(PokemonId::max_for(1_507_608_047).unwrap()));
+        assert!(id < base62(PokemonId::min_for(1_507_608_048).unwrap()));
+    }
+
+    #[test]
+    fn deserializes_both_representations() {
+        use serde_test::assert_de_tokens;
+
+        let id = known_id();
+        assert_de_tokens(&id.clone().readable(), &[Token::Str(BASE62)]);
+        assert_de_tokens(&id.compact(), &[Token::Bytes(&BYTES)]);
+    }
+
+    #[test]
+    fn serializes_as_base62_for_human_readable_formats() {
+        assert_ser_tokens(&known_id().readable(), &[Token::Str(BASE62)]);
+    }
+
+    #[test]
+    fn serializes_as_raw_bytes_for_binary_formats() {
+        assert_ser_tokens(&known_id().compact(), &[Token::Bytes(&BYTES)]);
     }
 }
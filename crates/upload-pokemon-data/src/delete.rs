@@ -0,0 +1,149 @@
+use color_eyre::{
+    eyre::{self, eyre},
+    Help,
+};
+use sqlx::{mysql::MySqlDatabaseError, MySqlPool};
+use tracing::{info, warn};
+
+use crate::db::PokemonId;
+
+/// `ER_NO_SUCH_TABLE`, raised when `tombstones` hasn't been created yet.
+const ER_NO_SUCH_TABLE: u16 = 1146;
+
+/// Deletes the pokemon with the given slugs, and everything in the child
+/// tables that points at them, in a single transaction. Each deleted slug
+/// gets a tombstone so the API answers 410 for it, unless the database
+/// predates the `tombstones` table.
+pub async fn delete(pool: MySqlPool, slugs: &[String], yes: bool) -> eyre::Result<()> {
+    if !yes {
+        return Err(eyre!("refusing to delete {} pokemon", slugs.len()))
+            .suggestion("Pass --yes to confirm the deletion");
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut deleted = 0;
+    for slug in slugs {
+        let id = sqlx::query_scalar!(
+            r#"SELECT id as "id!: PokemonId" FROM pokemon WHERE slug = ?"#,
+            slug
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(id) = id else {
+            warn!(slug = slug.as_str(), "no pokemon with this slug, skipping");
+            continue;
+        };
+
+        // children first, so nothing is left pointing at a missing pokemon
+        sqlx::query!("DELETE FROM abilities WHERE pokemon_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM typing WHERE pokemon_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM egg_groups WHERE pokemon_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM evolutions WHERE pokemon_id = ? OR evolves_from = ?",
+            id,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM pokemon WHERE id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        let tombstoned = sqlx::query!(
+            r#"
+INSERT INTO tombstones (slug, redirect_to, deleted_at)
+VALUES (?, NULL, CURRENT_TIMESTAMP)
+ON DUPLICATE KEY UPDATE redirect_to = NULL, deleted_at = CURRENT_TIMESTAMP
+"#,
+            slug
+        )
+        .execute(&mut *tx)
+        .await;
+        // a failed statement doesn't roll back a MySQL transaction, so the
+        // deletion still commits
+        match tombstoned {
+            Err(error) if is_missing_table_error(&error) => warn!(
+                slug = slug.as_str(),
+                "tombstones table does not exist, so no tombstone was left; run create-tables.sql"
+            ),
+            result => {
+                result?;
+            }
+        }
+
+        info!(slug = slug.as_str(), "deleted pokemon");
+        deleted += 1;
+    }
+    tx.commit().await?;
+
+    println!("{deleted} of {} pokemon deleted", slugs.len());
+    Ok(())
+}
+
+fn is_missing_table_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(error) => error
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .is_some_and(|error| error.number() == ER_NO_SUCH_TABLE),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{insert_pokemon, PokemonTable, PokemonTableRow};
+    use crate::test_db::db_test;
+
+    db_test! {
+        async fn deletes_children_and_skips_missing_slugs(pool: MySqlPool) {
+            let pikachu = insert_pokemon(
+                pool.clone(),
+                PokemonTable::Pokemon,
+                PokemonTableRow {
+                    name: "Pikachu".to_string(),
+                    slug: "pikachu".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO typing (id, pokemon_id, typing) VALUES (?, ?, 'Electric')")
+                .bind(PokemonId::new())
+                .bind(&pikachu.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            assert!(delete(pool.clone(), &["pikachu".to_string()], false)
+                .await
+                .is_err());
+            delete(
+                pool.clone(),
+                &["pikachu".to_string(), "missingno".to_string()],
+                true,
+            )
+            .await
+            .unwrap();
+
+            let remaining: i64 = sqlx::query_scalar(
+                "SELECT (SELECT COUNT(*) FROM pokemon) + (SELECT COUNT(*) FROM typing)",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(remaining, 0);
+
+            let tombstones: Vec<String> = sqlx::query_scalar("SELECT slug FROM tombstones")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            assert_eq!(tombstones, ["pikachu"]);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{self, WrapErr};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{db::slugify, pokemon_csv::PokemonCsv, read_json, DatasetFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// A summary for reading
+    Text,
+    /// The whole diff as JSON, for tooling
+    Json,
+}
+
+/// What loading `new` over `old` would do, by slug.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct DatasetDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<ChangedPokemon>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ChangedPokemon {
+    slug: String,
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct FieldChange {
+    field: String,
+    old: Value,
+    new: Value,
+}
+
+/// Reads a whole dataset, CSV or JSON by extension, keyed by slug.
+fn load(path: &Path) -> eyre::Result<BTreeMap<String, Map<String, Value>>> {
+    let pokemon: Vec<PokemonCsv> = match DatasetFormat::from_path(path) {
+        DatasetFormat::Csv => csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<Result<_, csv::Error>>()?,
+        DatasetFormat::Json => read_json(&fs::read_to_string(path)?, None)?,
+    };
+    pokemon
+        .into_iter()
+        .map(|pokemon| {
+            let slug = slugify("", &pokemon.name);
+            match serde_json::to_value(pokemon)? {
+                Value::Object(fields) => Ok((slug, fields)),
+                _ => unreachable!("pokemon serialize as objects"),
+            }
+        })
+        .collect()
+}
+
+fn diff_datasets(
+    old: &BTreeMap<String, Map<String, Value>>,
+    new: &BTreeMap<String, Map<String, Value>>,
+) -> DatasetDiff {
+    let mut diff = DatasetDiff::default();
+    for (slug, new_fields) in new {
+        let Some(old_fields) = old.get(slug) else {
+            diff.added.push(slug.clone());
+            continue;
+        };
+        let changes: Vec<FieldChange> = new_fields
+            .iter()
+            .filter(|(field, value)| old_fields.get(*field) != Some(value))
+            .map(|(field, value)| FieldChange {
+                field: field.clone(),
+                old: old_fields.get(field).cloned().unwrap_or(Value::Null),
+                new: value.clone(),
+            })
+            .collect();
+        if !changes.is_empty() {
+            diff.changed.push(ChangedPokemon {
+                slug: slug.clone(),
+                changes,
+            });
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|slug| !new.contains_key(*slug))
+        .cloned()
+        .collect();
+    diff
+}
+
+fn render_text(diff: &DatasetDiff) -> String {
+    let mut text = String::new();
+    for slug in &diff.added {
+        text.push_str(&format!("+ {slug}\n"));
+    }
+    for slug in &diff.removed {
+        text.push_str(&format!("- {slug}\n"));
+    }
+    for ChangedPokemon { slug, changes } in &diff.changed {
+        text.push_str(&format!("~ {slug}\n"));
+        for FieldChange { field, old, new } in changes {
+            text.push_str(&format!("    {field}: {old} -> {new}\n"));
+        }
+    }
+    text.push_str(&format!(
+        "{} added, {} removed, {} changed\n",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    ));
+    text
+}
+
+/// Prints what changed between two datasets without touching the database.
+pub fn diff(old: &Path, new: &Path, format: DiffFormat) -> eyre::Result<()> {
+    let old_pokemon = load(old).wrap_err_with(|| format!("could not read {}", old.display()))?;
+    let new_pokemon = load(new).wrap_err_with(|| format!("could not read {}", new.display()))?;
+    let diff = diff_datasets(&old_pokemon, &new_pokemon);
+
+    match format {
+        DiffFormat::Text => print!("{}", render_text(&diff)),
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dataset(pokemon: Value) -> BTreeMap<String, Map<String, Value>> {
+        serde_json::from_value(pokemon).unwrap()
+    }
+
+    #[test]
+    fn lists_added_removed_and_changed_fields() {
+        let old = dataset(json!({
+            "pikachu": { "hp": 35, "speed": 90 },
+            "raichu": { "hp": 60, "speed": 110 },
+            "mew": { "hp": 100, "speed": 100 }
+        }));
+        let new = dataset(json!({
+            "pikachu": { "hp": 35, "speed": 95 },
+            "raichu": { "hp": 60, "speed": 110 },
+            "pichu": { "hp": 20, "speed": 60 }
+        }));
+
+        let diff = diff_datasets(&old, &new);
+        assert_eq!(
+            diff,
+            DatasetDiff {
+                added: vec!["pichu".to_string()],
+                removed: vec!["mew".to_string()],
+                changed: vec![ChangedPokemon {
+                    slug: "pikachu".to_string(),
+                    changes: vec![FieldChange {
+                        field: "speed".to_string(),
+                        old: json!(90),
+                        new: json!(95),
+                    }],
+                }],
+            }
+        );
+        assert_eq!(
+            render_text(&diff),
+            "+ pichu\n- mew\n~ pikachu\n    speed: 90 -> 95\n1 added, 1 removed, 1 changed\n"
+        );
+    }
+}
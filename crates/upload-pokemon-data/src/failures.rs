@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use color_eyre::{
+    eyre::{self, eyre, WrapErr},
+    Help,
+};
+use tracing::warn;
+
+/// What to do when a pokemon, or one of its abilities, types, egg groups or
+/// evolutions, fails to insert.
+#[derive(Debug)]
+pub struct Failures {
+    continue_on_error: bool,
+    /// The first error for each slug that failed, sorted for the report.
+    by_slug: BTreeMap<String, Failure>,
+}
+
+#[derive(Debug)]
+struct Failure {
+    error: String,
+    /// The rows that hang off the pokemon and so weren't written either.
+    skipped: Vec<String>,
+}
+
+impl Failures {
+    pub fn new(continue_on_error: bool) -> Self {
+        Self {
+            continue_on_error,
+            by_slug: BTreeMap::new(),
+        }
+    }
+
+    /// `Ok(Some)` for a success. A failure aborts the upload, or with
+    /// `--continue-on-error` is remembered and `Ok(None)` returned.
+    pub fn check<T>(
+        &mut self,
+        slug: String,
+        result: Result<T, sqlx::Error>,
+    ) -> eyre::Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if self.continue_on_error => {
+                warn!(slug, %error, "could not upload pokemon, continuing");
+                self.by_slug.entry(slug).or_insert_with(|| Failure {
+                    error: error.to_string(),
+                    skipped: Vec::new(),
+                });
+                Ok(None)
+            }
+            Err(error) => Err(error).wrap_err_with(|| format!("could not upload `{slug}`")),
+        }
+    }
+
+    /// Whether `slug` has failed, so whatever hangs off it should be skipped.
+    pub fn has_failed(&self, slug: &str) -> bool {
+        self.by_slug.contains_key(slug)
+    }
+
+    /// Records `rows` as not written because `slug` failed, to list them with
+    /// its error.
+    pub fn skipped(&mut self, slug: &str, rows: String) {
+        if let Some(failure) = self.by_slug.get_mut(slug) {
+            failure.skipped.push(rows);
+        }
+    }
+
+    /// Lists every slug that failed, as an error so the process exits
+    /// non-zero.
+    pub fn finish(self) -> eyre::Result<()> {
+        if self.by_slug.is_empty() {
+            return Ok(());
+        }
+        for (slug, failure) in &self.by_slug {
+            eprintln!("{slug}: {}", failure.error);
+            for rows in &failure.skipped {
+                eprintln!("  skipped {rows}");
+            }
+        }
+        let slugs: Vec<_> = self.by_slug.into_keys().collect();
+        Err(eyre!(
+            "{} pokemon failed to upload: {}",
+            slugs.len(),
+            slugs.join(", ")
+        ))
+        .suggestion("Fix those rows and upload again, the rest are already in the database")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_fast_stops_at_the_first_error() {
+        let mut failures = Failures::new(false);
+        assert_eq!(
+            failures.check("bulbasaur".to_string(), Ok(1)).unwrap(),
+            Some(1)
+        );
+        assert!(failures
+            .check::<()>("ivysaur".to_string(), Err(sqlx::Error::RowNotFound))
+            .is_err());
+    }
+
+    #[test]
+    fn continue_on_error_reports_each_failed_slug_once() {
+        let mut failures = Failures::new(true);
+        for slug in ["venusaur", "ivysaur", "venusaur"] {
+            let result = failures.check::<()>(slug.to_string(), Err(sqlx::Error::RowNotFound));
+            assert_eq!(result.unwrap(), None);
+        }
+        failures.check("bulbasaur".to_string(), Ok(())).unwrap();
+
+        let error = failures.finish().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "2 pokemon failed to upload: ivysaur, venusaur"
+        );
+        assert!(Failures::new(true).finish().is_ok());
+    }
+
+    #[test]
+    fn skipped_rows_are_listed_with_the_failure() {
+        let mut failures = Failures::new(true);
+        failures
+            .check::<()>("ivysaur".to_string(), Err(sqlx::Error::RowNotFound))
+            .unwrap();
+        assert!(failures.has_failed("ivysaur"));
+        assert!(!failures.has_failed("bulbasaur"));
+
+        failures.skipped(
+            "ivysaur",
+            "its abilities, egg groups and typing".to_string(),
+        );
+        failures.skipped("bulbasaur", "its evolution".to_string());
+        assert_eq!(
+            failures.by_slug["ivysaur"].skipped,
+            ["its abilities, egg groups and typing"]
+        );
+        assert!(!failures.by_slug.contains_key("bulbasaur"));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::db::{insert_pokemon, PokemonId, PokemonTable, PokemonTableRow, Stat};
+use color_eyre::eyre;
+use futures::{stream::FuturesUnordered, StreamExt};
+use indicatif::ProgressBar;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use sqlx::MySqlPool;
+
+const COLORS: [&str; 10] = [
+    "black", "blue", "brown", "gray", "green", "pink", "purple", "red", "white", "yellow",
+];
+
+/// Neutral matchups are by far the most common, so they are over-represented.
+const EFFECTIVENESS: [f32; 8] = [0.0, 0.25, 0.5, 1.0, 1.0, 1.0, 2.0, 4.0];
+
+/// Fabricates `count` plausible pokemon named `fakemon-0001` onwards.
+///
+/// The stats come from `rng`, so a seeded rng always produces the same
+/// pokemon. Ids are still random ksuids.
+pub fn fakemon(count: usize, rng: &mut impl Rng) -> Vec<PokemonTableRow> {
+    (1..=count)
+        .map(|number| {
+            let genderless = rng.gen_bool(0.1);
+            PokemonTableRow {
+                id: PokemonId::new(),
+                name: format!("Fakemon {number:04}"),
+                slug: format!("fakemon-{number:04}"),
+                pokedex_id: rng.gen_range(1..=1010),
+                hp: Stat(rng.gen_range(1..=255)),
+                attack: Stat(rng.gen_range(5..=190)),
+                defense: Stat(rng.gen_range(5..=230)),
+                special_attack: Stat(rng.gen_range(10..=194)),
+                special_defense: Stat(rng.gen_range(20..=230)),
+                speed: Stat(rng.gen_range(5..=200)),
+                height: rng.gen_range(1..=200),
+                weight: rng.gen_range(1..=9999),
+                generation: rng.gen_range(1..=9),
+                female_rate: (!genderless).then(|| f32::from(rng.gen_range(0u8..=8)) / 8.0),
+                genderless,
+                legendary_or_mythical: rng.gen_bool(0.05),
+                is_default: true,
+                forms_switchable: false,
+                base_experience: rng.gen_range(36..=608),
+                capture_rate: rng.gen_range(3..=255),
+                base_happiness: *[0, 35, 50, 70, 100, 140].choose(rng).unwrap(),
+                primary_color: COLORS.choose(rng).unwrap().to_string(),
+                number_pokemon_with_typing: f32::from(rng.gen_range(1u8..=120)),
+                normal_attack_effectiveness: effectiveness(rng),
+                fire_attack_effectiveness: effectiveness(rng),
+                water_attack_effectiveness: effectiveness(rng),
+                electric_attack_effectiveness: effectiveness(rng),
+                grass_attack_effectiveness: effectiveness(rng),
+                ice_attack_effectiveness: effectiveness(rng),
+                fighting_attack_effectiveness: effectiveness(rng),
+                poison_attack_effectiveness: effectiveness(rng),
+                ground_attack_effectiveness: effectiveness(rng),
+                fly_attack_effectiveness: effectiveness(rng),
+                psychic_attack_effectiveness: effectiveness(rng),
+                bug_attack_effectiveness: effectiveness(rng),
+                rock_attack_effectiveness: effectiveness(rng),
+                ghost_attack_effectiveness: effectiveness(rng),
+                dragon_attack_effectiveness: effectiveness(rng),
+                dark_attack_effectiveness: effectiveness(rng),
+                steel_attack_effectiveness: effectiveness(rng),
+                fairy_attack_effectiveness: effectiveness(rng),
+            }
+        })
+        .collect()
+}
+
+fn effectiveness(rng: &mut impl Rng) -> f32 {
+    *EFFECTIVENESS.choose(rng).unwrap()
+}
+
+/// Inserts `count` fabricated pokemon, e.g. for load testing the list
+/// endpoints.
+pub async fn generate(pool: MySqlPool, count: usize, seed: Option<u64>) -> eyre::Result<()> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut tasks = fakemon(count, &mut rng)
+        .into_iter()
+        .map(|row| tokio::spawn(insert_pokemon(pool.clone(), PokemonTable::Pokemon, row)))
+        .collect::<FuturesUnordered<_>>();
+
+    let pb = ProgressBar::new(tasks.len() as u64);
+    while let Some(item) = tasks.next().await {
+        item??;
+        pb.inc(1);
+    }
+    pb.finish();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_fakemon_are_reproducible() {
+        let first = fakemon(3, &mut StdRng::seed_from_u64(7));
+        let second = fakemon(3, &mut StdRng::seed_from_u64(7));
+
+        let slugs: Vec<_> = first.iter().map(|row| row.slug.as_str()).collect();
+        assert_eq!(slugs, ["fakemon-0001", "fakemon-0002", "fakemon-0003"]);
+        for (first, second) in first.iter().zip(&second) {
+            assert_eq!(first.hp, second.hp);
+            assert_eq!(first.attack, second.attack);
+            assert_eq!(first.primary_color, second.primary_color);
+            assert_eq!(
+                first.water_attack_effectiveness,
+                second.water_attack_effectiveness
+            );
+        }
+    }
+}
@@ -1,4 +1,16 @@
+mod connect_options;
 mod db;
 mod pokemon_csv;
+mod pokemon_type;
+#[cfg(test)]
+mod test_db;
+mod tls;
 
-pub use db::PokemonId;
\ No newline at end of file
+pub use connect_options::connect_options_from_env;
+pub use db::{
+    insert_pokemon, insert_pokemon_tx, set_localized_names, Effectiveness, FloatFormat,
+    InsertAction, InsertOutcome, LocalizedNames, PokemonId, PokemonIdError, PokemonTable,
+    PokemonTableRow, Stat, POKEMON_COLUMNS,
+};
+pub use pokemon_type::{PokemonType, UnknownPokemonType};
+pub use tls::tls_from_env;
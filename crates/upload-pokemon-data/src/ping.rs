@@ -0,0 +1,46 @@
+use std::time::Instant;
+
+use color_eyre::{
+    eyre::{self, WrapErr},
+    Help,
+};
+use sqlx::MySqlPool;
+use tracing::info;
+
+/// Runs `SELECT 1` and prints the server's version, so a deploy can check the
+/// credentials, TLS settings and network path before a real load.
+pub async fn ping(pool: MySqlPool) -> eyre::Result<()> {
+    let started = Instant::now();
+    let version = server_version(&pool)
+        .await
+        .wrap_err("connected, but the server didn't answer `SELECT 1`")
+        .suggestion("Check the user in DATABASE_URL can run queries on this database")?;
+    info!(elapsed_ms = started.elapsed().as_millis() as u64, "pinged");
+    println!("ok: MySQL {version}");
+    Ok(())
+}
+
+async fn server_version(pool: &MySqlPool) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT 1")
+        .fetch_one(pool)
+        .await?;
+    sqlx::query_scalar("SELECT VERSION()").fetch_one(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = false)]
+    #[cfg_attr(
+        not(feature = "db-tests"),
+        ignore = "set DATABASE_URL and enable db-tests"
+    )]
+    async fn answers_with_the_server_version(pool: MySqlPool) {
+        let version = server_version(&pool).await.unwrap();
+        assert!(
+            version.starts_with(|c: char| c.is_ascii_digit()),
+            "{version}"
+        );
+    }
+}
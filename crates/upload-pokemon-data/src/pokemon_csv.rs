@@ -1,36 +1,78 @@
-use serde::{de, Deserialize};
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDate;
+use serde::{de, Deserialize, Serialize};
+
+use crate::{db::LocalizedNames, pokemon_type::PokemonType};
 
 fn from_capital_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: de::Deserializer<'de>,
 {
-    let s: &str = de::Deserialize::deserialize(deserializer)?;
-    match s {
+    let s: String = de::Deserialize::deserialize(deserializer)?;
+    match s.trim() {
         "True" => Ok(true),
         "False" => Ok(false),
         _ => Err(serde::de::Error::custom("not a boolean!")),
     }
 }
 
-fn from_comma_seperated<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+fn from_comma_seperated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     D: de::Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
 {
-    let s: &str = de::Deserialize::deserialize(deserializer)?;
-    Ok(s.split(", ")
+    let s: String = de::Deserialize::deserialize(deserializer)?;
+    s.split(',')
+        .map(str::trim)
         .filter(|v| !v.is_empty())
-        .map(|v| v.to_string())
-        .collect())
+        .map(|v| v.parse().map_err(de::Error::custom))
+        .collect()
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Strips stray spaces around a field, keeping the ones inside it (`Mr
+/// Mime`), so a padded name still slugs the same.
+fn trimmed<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let s: String = de::Deserialize::deserialize(deserializer)?;
+    Ok(s.trim().to_string())
+}
+
+fn trimmed_option<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let s: Option<String> = de::Deserialize::deserialize(deserializer)?;
+    Ok(s.as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string))
+}
+
+/// One row of the dataset. JSON datasets use the same keys and the same
+/// string forms (`"True"`, `"Grass, Poison"`), so the fields are read as owned
+/// strings: JSON strings with escapes can't be borrowed.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PokemonCsv {
+    #[serde(deserialize_with = "trimmed")]
     pub name: String,
+    /// Optional columns with the name in other languages.
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub name_ja: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub name_fr: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub name_de: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    pub name_es: Option<String>,
     pub pokedex_id: u16,
     #[serde(deserialize_with = "from_comma_seperated")]
     pub abilities: Vec<String>,
     #[serde(deserialize_with = "from_comma_seperated")]
-    pub typing: Vec<String>,
+    pub typing: Vec<PokemonType>,
     pub hp: u8,
     pub attack: u8,
     pub defense: u8,
@@ -57,7 +99,9 @@ pub struct PokemonCsv {
     #[serde(deserialize_with = "from_comma_seperated")]
     pub egg_groups: Vec<String>,
     pub base_happiness: u8,
+    #[serde(deserialize_with = "trimmed_option")]
     pub evolves_from: Option<String>,
+    #[serde(deserialize_with = "trimmed")]
     pub primary_color: String,
     pub number_pokemon_with_typing: f32,
     pub normal_attack_effectiveness: f32,
@@ -78,4 +122,48 @@ pub struct PokemonCsv {
     pub dark_attack_effectiveness: f32,
     pub steel_attack_effectiveness: f32,
     pub fairy_attack_effectiveness: f32,
+    // when a row was edited isn't part of what the row says
+    #[serde(default, skip_serializing)]
+    pub last_modified: Option<NaiveDate>,
+}
+
+impl PokemonCsv {
+    pub fn localized_names(&self) -> LocalizedNames {
+        LocalizedNames {
+            ja: self.name_ja.clone(),
+            fr: self.name_fr.clone(),
+            de: self.name_de.clone(),
+            es: self.name_es.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "name,pokedex_id,abilities,typing,hp,attack,defense,special_attack,special_defense,speed,height,weight,generation,female_rate,genderless,legendary/mythical,is_default,forms_switchable,base_experience,capture_rate,egg_groups,base_happiness,evolves_from,primary_color,number_pokemon_with_typing,normal_attack_effectiveness,fire_attack_effectiveness,water_attack_effectiveness,electric_attack_effectiveness,grass_attack_effectiveness,ice_attack_effectiveness,fighting_attack_effectiveness,poison_attack_effectiveness,ground_attack_effectiveness,fly_attack_effectiveness,psychic_attack_effectiveness,bug_attack_effectiveness,rock_attack_effectiveness,ghost_attack_effectiveness,dragon_attack_effectiveness,dark_attack_effectiveness,steel_attack_effectiveness,fairy_attack_effectiveness";
+
+    #[test]
+    fn trims_padded_string_fields() {
+        let csv = format!(
+            "{HEADER}\n \
+            Mr Mime ,122,\" Soundproof ,Filter \",\" Psychic, Fairy\",40,45,65,100,120,90,13,\
+            545,1,0.5, False ,False,True,False,161,45,Human-Like,50, Mime Jr ,\
+            pink ,6.0,1.0,1.0,1.0,1.0,1.0,1.0,0.25,1.0,1.0,1.0,0.5,2.0,1.0,2.0,0.0,1.0,2.0,1.0\n"
+        );
+
+        let pokemon: PokemonCsv = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(pokemon.name, "Mr Mime");
+        assert_eq!(pokemon.abilities, ["Soundproof", "Filter"]);
+        assert_eq!(pokemon.typing, [PokemonType::Psychic, PokemonType::Fairy]);
+        assert!(!pokemon.genderless);
+        assert_eq!(pokemon.evolves_from.as_deref(), Some("Mime Jr"));
+        assert_eq!(pokemon.primary_color, "pink");
+        assert_eq!(pokemon.localized_names(), LocalizedNames::default());
+    }
 }
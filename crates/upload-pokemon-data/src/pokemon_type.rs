@@ -0,0 +1,201 @@
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+use sqlx::{
+    encode::IsNull,
+    mysql::{MySql, MySqlTypeInfo},
+    Decode, Encode, Type,
+};
+
+/// The eighteen pokemon types, in the same order as the effectiveness columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PokemonType {
+    Normal,
+    Fire,
+    Water,
+    Electric,
+    Grass,
+    Ice,
+    Fighting,
+    Poison,
+    Ground,
+    Flying,
+    Psychic,
+    Bug,
+    Rock,
+    Ghost,
+    Dragon,
+    Dark,
+    Steel,
+    Fairy,
+}
+
+impl PokemonType {
+    pub const ALL: [PokemonType; 18] = [
+        PokemonType::Normal,
+        PokemonType::Fire,
+        PokemonType::Water,
+        PokemonType::Electric,
+        PokemonType::Grass,
+        PokemonType::Ice,
+        PokemonType::Fighting,
+        PokemonType::Poison,
+        PokemonType::Ground,
+        PokemonType::Flying,
+        PokemonType::Psychic,
+        PokemonType::Bug,
+        PokemonType::Rock,
+        PokemonType::Ghost,
+        PokemonType::Dragon,
+        PokemonType::Dark,
+        PokemonType::Steel,
+        PokemonType::Fairy,
+    ];
+
+    /// The name as the dataset and the `typing` table spell it, e.g. `Fire`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PokemonType::Normal => "Normal",
+            PokemonType::Fire => "Fire",
+            PokemonType::Water => "Water",
+            PokemonType::Electric => "Electric",
+            PokemonType::Grass => "Grass",
+            PokemonType::Ice => "Ice",
+            PokemonType::Fighting => "Fighting",
+            PokemonType::Poison => "Poison",
+            PokemonType::Ground => "Ground",
+            PokemonType::Flying => "Flying",
+            PokemonType::Psychic => "Psychic",
+            PokemonType::Bug => "Bug",
+            PokemonType::Rock => "Rock",
+            PokemonType::Ghost => "Ghost",
+            PokemonType::Dragon => "Dragon",
+            PokemonType::Dark => "Dark",
+            PokemonType::Steel => "Steel",
+            PokemonType::Fairy => "Fairy",
+        }
+    }
+
+    /// The `pokemon` column holding how effective attacks of this type are.
+    pub fn effectiveness_column(self) -> &'static str {
+        match self {
+            PokemonType::Normal => "normal_attack_effectiveness",
+            PokemonType::Fire => "fire_attack_effectiveness",
+            PokemonType::Water => "water_attack_effectiveness",
+            PokemonType::Electric => "electric_attack_effectiveness",
+            PokemonType::Grass => "grass_attack_effectiveness",
+            PokemonType::Ice => "ice_attack_effectiveness",
+            PokemonType::Fighting => "fighting_attack_effectiveness",
+            PokemonType::Poison => "poison_attack_effectiveness",
+            PokemonType::Ground => "ground_attack_effectiveness",
+            PokemonType::Flying => "fly_attack_effectiveness",
+            PokemonType::Psychic => "psychic_attack_effectiveness",
+            PokemonType::Bug => "bug_attack_effectiveness",
+            PokemonType::Rock => "rock_attack_effectiveness",
+            PokemonType::Ghost => "ghost_attack_effectiveness",
+            PokemonType::Dragon => "dragon_attack_effectiveness",
+            PokemonType::Dark => "dark_attack_effectiveness",
+            PokemonType::Steel => "steel_attack_effectiveness",
+            PokemonType::Fairy => "fairy_attack_effectiveness",
+        }
+    }
+}
+
+impl fmt::Display for PokemonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPokemonType(pub String);
+
+impl fmt::Display for UnknownPokemonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a pokemon type, expected one of ", self.0)?;
+        for (i, pokemon_type) in PokemonType::ALL.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", pokemon_type.name().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownPokemonType {}
+
+/// Case-insensitive, so both `Fire` from the dataset and `fire` from a query
+/// string parse.
+impl FromStr for PokemonType {
+    type Err = UnknownPokemonType;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PokemonType::ALL
+            .into_iter()
+            .find(|pokemon_type| pokemon_type.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| UnknownPokemonType(name.to_string()))
+    }
+}
+
+impl<'q> Encode<'q, MySql> for PokemonType {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
+        <&str as Encode<MySql>>::encode(self.name(), buf)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for PokemonType {
+    fn decode(
+        value: <MySql as sqlx::database::HasValueRef<'r>>::ValueRef,
+    ) -> Result<PokemonType, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        Ok(<&str as Decode<MySql>>::decode(value)?.parse()?)
+    }
+}
+
+impl Type<MySql> for PokemonType {
+    fn type_info() -> MySqlTypeInfo {
+        <&str as Type<MySql>>::type_info()
+    }
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <&str as Type<MySql>>::compatible(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::db_test;
+    use sqlx::MySqlPool;
+
+    #[test]
+    fn parses_any_case_and_rejects_unknown_types() {
+        assert_eq!("Fire".parse(), Ok(PokemonType::Fire));
+        assert_eq!("flying".parse(), Ok(PokemonType::Flying));
+        assert_eq!(
+            "Sound".parse::<PokemonType>(),
+            Err(UnknownPokemonType("Sound".to_string()))
+        );
+        for pokemon_type in PokemonType::ALL {
+            assert_eq!(pokemon_type.to_string().parse(), Ok(pokemon_type));
+        }
+    }
+
+    db_test! {
+        async fn round_trips_through_the_typing_table(pool: MySqlPool) {
+            sqlx::query("INSERT INTO typing (id, pokemon_id, typing) VALUES (?, ?, ?)")
+                .bind("0ujtsYcgvSTl8PAuAdqWYSMnLOv")
+                .bind("0ujsszwN8NRY24YaXiTIE2VWDTS")
+                .bind(PokemonType::Psychic)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let typing: PokemonType = sqlx::query_scalar("SELECT typing FROM typing")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            assert_eq!(typing, PokemonType::Psychic);
+        }
+    }
+}
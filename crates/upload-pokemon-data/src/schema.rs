@@ -0,0 +1,96 @@
+use std::{fmt, fs, path::Path};
+
+use color_eyre::eyre::{self, eyre, WrapErr};
+use jsonschema::JSONSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+/// One place a row of the dataset breaks the `--schema` contract.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaViolation {
+    /// 1-based, counting data rows only, like `ValidationError`.
+    pub row: usize,
+    /// JSON pointer to the offending value inside the row, `/hp` or
+    /// `/typing/2`; empty when the row as a whole is wrong.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "row {}, {pointer}: {}", self.row, self.message)
+    }
+}
+
+/// Reads and compiles a JSON Schema, so a broken schema fails before any row
+/// is checked against it.
+pub fn load(path: &Path) -> eyre::Result<JSONSchema> {
+    let schema: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(path)
+            .wrap_err_with(|| format!("could not read the schema {}", path.display()))?,
+    )
+    .wrap_err_with(|| format!("{} is not JSON", path.display()))?;
+    JSONSchema::compile(&schema)
+        .map_err(|error| eyre!("{} is not a valid JSON Schema: {error}", path.display()))
+}
+
+/// Every violation of `schema` in the dataset, in row order. Rows are checked
+/// as they were written rather than as they were parsed, so `typing` is the
+/// comma separated string and the legendary flag is `legendary/mythical`,
+/// and a schema can catch what parsing would have quietly fixed up.
+pub fn validate_dataset(schema: &JSONSchema, records: &[Value]) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        if let Err(errors) = schema.validate(record) {
+            violations.extend(errors.map(|error| SchemaViolation {
+                row: index + 1,
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            }));
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_violations_with_pointers() {
+        let records: Vec<Value> = csv::Reader::from_path("pokemon.csv")
+            .unwrap()
+            .deserialize()
+            .map(|record| record.map(Value::Object))
+            .take(3)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let schema = JSONSchema::compile(&serde_json::json!({
+            "type": "object",
+            "required": ["name", "typing"],
+            "properties": {
+                "typing": { "type": "string" },
+                "legendary/mythical": { "enum": ["True", "False"] },
+                "hp": { "type": "integer", "maximum": 44 }
+            }
+        }))
+        .unwrap();
+
+        let violations = validate_dataset(&schema, &records);
+        // bulbasaur, ivysaur and venusaur all have more than 44 hp
+        let rows: Vec<_> = violations.iter().map(|violation| violation.row).collect();
+        assert_eq!(rows, [1, 2, 3]);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.pointer == "/hp"));
+        assert_eq!(
+            violations[0].to_string(),
+            format!("row 1, /hp: {}", violations[0].message)
+        );
+    }
+}
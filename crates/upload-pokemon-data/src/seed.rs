@@ -0,0 +1,123 @@
+use std::{env, ffi::OsString, fs, process};
+
+use clap::Parser;
+use color_eyre::{
+    eyre::{self, eyre},
+    Help,
+};
+use sqlx::{Executor, MySqlPool};
+use tracing::info;
+
+use crate::Cli;
+
+/// The pokemon the API's handler tests load, bulbasaur, squirtle and ho-oh
+/// with the same stats, typing and localized names, and rows that go through
+/// the loader's edge cases: ho-oh and `151` are genderless, vulpix has an
+/// alternate form, `151` slugifies to all digits, flabébé isn't ASCII and
+/// bulbasaur leaves every optional field empty.
+const SEED: &str = include_str!("../seed.csv");
+
+/// Every table `create-tables.sql` creates, emptied before the fixture loads.
+const TABLES: [&str; 7] = [
+    "abilities",
+    "typing",
+    "egg_groups",
+    "evolutions",
+    "tombstones",
+    "pokemon",
+    "pokemon_staging",
+];
+
+/// Empties every table and loads the seed dataset, so local and CI databases
+/// start from the same rows however they were used before.
+pub async fn seed_test_db(pool: MySqlPool, yes: bool) -> eyre::Result<()> {
+    if !yes {
+        return Err(eyre!("refusing to empty every table"))
+            .suggestion("Pass --yes to confirm, and never point this at a real database");
+    }
+
+    for table in TABLES {
+        info!(table, "truncating");
+        pool.execute(format!("TRUNCATE TABLE {table}").as_str())
+            .await?;
+    }
+    // loaded the way a real dataset is, so the seed goes through the same
+    // slugs, validation and inserts
+    let dataset = env::temp_dir().join(format!("seed-{}.csv", process::id()));
+    fs::write(&dataset, SEED)?;
+    let args = Cli::try_parse_from([
+        OsString::from("upload-pokemon-data"),
+        OsString::from("--dataset"),
+        dataset.clone().into_os_string(),
+    ])?
+    .upload;
+    let loaded = crate::upload(pool.clone(), args).await;
+    fs::remove_file(&dataset)?;
+    loaded?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pokemon")
+        .fetch_one(&pool)
+        .await?;
+    println!("seeded {count} pokemon");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{insert_pokemon, PokemonTable, PokemonTableRow};
+    use crate::test_db::db_test;
+
+    db_test! {
+        async fn reseeding_replaces_whatever_was_there(pool: MySqlPool) {
+            insert_pokemon(
+                pool.clone(),
+                PokemonTable::Pokemon,
+                PokemonTableRow {
+                    name: "Pikachu".to_string(),
+                    slug: "pikachu".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            assert!(seed_test_db(pool.clone(), false).await.is_err());
+
+            seed_test_db(pool.clone(), true).await.unwrap();
+            seed_test_db(pool.clone(), true).await.unwrap();
+
+            let slugs: Vec<String> =
+                sqlx::query_scalar("SELECT slug FROM pokemon ORDER BY pokedex_id, is_default DESC")
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(
+                slugs,
+                [
+                    "bulbasaur",
+                    "squirtle",
+                    "vulpix",
+                    "vulpix-alola",
+                    "ho-oh",
+                    "flabébé",
+                    "pokemon-151"
+                ]
+            );
+            let name_ja: Option<String> =
+                sqlx::query_scalar("SELECT name_ja FROM pokemon WHERE slug = 'squirtle'")
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(name_ja.as_deref(), Some("ゼニガメ"));
+            let typing: Vec<String> = sqlx::query_scalar(
+                "SELECT typing.typing FROM typing \
+                JOIN pokemon ON pokemon.id = typing.pokemon_id \
+                WHERE pokemon.slug = 'ho-oh' ORDER BY typing.typing",
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            assert_eq!(typing, ["Fire", "Flying"]);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::db::slugify;
+
+/// Two pokemon in the dataset whose names make the same slug, which `slug`
+/// being `UNIQUE` would otherwise only reject halfway through the load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlugCollision {
+    pub slug: String,
+    pub first: String,
+    pub second: String,
+}
+
+impl fmt::Display for SlugCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` and `{}` both slugify to `{}`",
+            self.first, self.second, self.slug
+        )
+    }
+}
+
+/// The slug of each of `names`, in order.
+///
+/// A name whose slug an earlier name already has is a collision, unless
+/// `disambiguate` is set, when it gets the first free `-2`, `-3`... suffix
+/// instead. Suffixes never take a slug another name makes on its own, and
+/// depend only on dataset order, so reloading the same dataset gives every
+/// pokemon the same slug again. A slug that would be all digits gets a
+/// `pokemon-` prefix, since the API reads an all-digit key as a pokedex id.
+pub fn assign_slugs(
+    prefix: &str,
+    names: &[&str],
+    disambiguate: bool,
+) -> Result<Vec<String>, Vec<SlugCollision>> {
+    let natural: Vec<String> = names
+        .iter()
+        .map(|name| match slugify(prefix, name) {
+            slug if reads_as_pokedex_id(&slug) => format!("pokemon-{slug}"),
+            slug => slug,
+        })
+        .collect();
+    let reserved: HashSet<&str> = natural.iter().map(String::as_str).collect();
+    let mut owners: HashMap<String, &str> = HashMap::new();
+    let mut slugs = Vec::with_capacity(names.len());
+    let mut collisions = Vec::new();
+
+    for (name, slug) in names.iter().zip(&natural) {
+        let Some(first) = owners.get(slug) else {
+            owners.insert(slug.clone(), name);
+            slugs.push(slug.clone());
+            continue;
+        };
+        if !disambiguate {
+            collisions.push(SlugCollision {
+                slug: slug.clone(),
+                first: first.to_string(),
+                second: name.to_string(),
+            });
+            continue;
+        }
+        let suffixed = (2..)
+            .map(|n| format!("{slug}-{n}"))
+            .find(|suffixed| {
+                !reserved.contains(suffixed.as_str()) && !owners.contains_key(suffixed)
+            })
+            .expect("there is always a free suffix");
+        owners.insert(suffixed.clone(), name);
+        slugs.push(suffixed);
+    }
+
+    if collisions.is_empty() {
+        Ok(slugs)
+    } else {
+        Err(collisions)
+    }
+}
+
+/// Whether the API would take `slug` for a pokedex id, since
+/// `/api/pokemon/:key` reads any all-digit key as one.
+fn reads_as_pokedex_id(slug: &str) -> bool {
+    !slug.is_empty() && slug.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_or_suffixes_colliding_slugs() {
+        let names = ["Mr Mime", "Mr. Mime", "Mr Mime 2", "Mr-Mime"];
+
+        assert_eq!(
+            assign_slugs("", &names, false).unwrap_err(),
+            [
+                SlugCollision {
+                    slug: "mr-mime".to_string(),
+                    first: "Mr Mime".to_string(),
+                    second: "Mr. Mime".to_string(),
+                },
+                SlugCollision {
+                    slug: "mr-mime".to_string(),
+                    first: "Mr Mime".to_string(),
+                    second: "Mr-Mime".to_string(),
+                },
+            ]
+        );
+        // `mr-mime-2` is already the slug of `Mr Mime 2`, so it's skipped
+        assert_eq!(
+            assign_slugs("", &names, true).unwrap(),
+            ["mr-mime", "mr-mime-3", "mr-mime-2", "mr-mime-4"]
+        );
+        assert_eq!(
+            assign_slugs("fan-", &["Pikachu", "Raichu"], false).unwrap(),
+            ["fan-pikachu", "fan-raichu"]
+        );
+    }
+
+    #[test]
+    fn all_digit_slugs_are_prefixed() {
+        assert!(reads_as_pokedex_id("151"));
+        assert!(reads_as_pokedex_id("007"));
+        assert!(!reads_as_pokedex_id("porygon2"));
+        assert!(!reads_as_pokedex_id("fan-151"));
+        assert!(!reads_as_pokedex_id(""));
+
+        assert_eq!(
+            assign_slugs("", &["151", "Porygon2"], false).unwrap(),
+            ["pokemon-151", "porygon2"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fs, path::Path};
+
+use color_eyre::{
+    eyre::{self, eyre, WrapErr},
+    Help,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::{error, info};
+
+/// The pokemon checked when no `--expectations` file is given.
+const DEFAULT_EXPECTATIONS: &str = include_str!("../smoke.json");
+
+/// One pokemon to request and the fields its response must contain. Fields
+/// not listed are not checked.
+#[derive(Debug, Deserialize)]
+struct Expectation {
+    slug: String,
+    expected: Map<String, Value>,
+}
+
+/// Every expected field the body got wrong, as `(field, expected, actual)`.
+fn mismatches<'a>(
+    expected: &'a Map<String, Value>,
+    body: &'a Value,
+) -> Vec<(&'a str, &'a Value, &'a Value)> {
+    expected
+        .iter()
+        .filter_map(|(field, expected)| {
+            let actual = body.get(field).unwrap_or(&Value::Null);
+            (actual != expected).then_some((field.as_str(), expected, actual))
+        })
+        .collect()
+}
+
+/// Requests each pokemon from the deployed API at `url` and fails if any
+/// answer is not a 200 with the expected fields.
+pub async fn smoke(url: &str, expectations: Option<&Path>) -> eyre::Result<()> {
+    let expectations = match expectations {
+        Some(path) => fs::read_to_string(path)
+            .wrap_err_with(|| format!("could not read {}", path.display()))?,
+        None => DEFAULT_EXPECTATIONS.to_string(),
+    };
+    let expectations: Vec<Expectation> = serde_json::from_str(&expectations)
+        .wrap_err("could not parse the expectations")
+        .suggestion(
+            r#"expectations look like `[{ "slug": "ho-oh", "expected": { "hp": 106 } }]`"#,
+        )?;
+
+    let client = reqwest::Client::new();
+    let mut failures = 0;
+    for Expectation { slug, expected } in &expectations {
+        let url = format!("{}/api/pokemon/{slug}", url.trim_end_matches('/'));
+        let response = client.get(&url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            error!(slug = slug.as_str(), %status, "unexpected status");
+            failures += 1;
+            continue;
+        }
+
+        let body: Value = response.json().await?;
+        let wrong = mismatches(expected, &body);
+        for (field, expected, actual) in &wrong {
+            error!(slug = slug.as_str(), field, %expected, %actual, "unexpected value");
+        }
+        if wrong.is_empty() {
+            info!(slug = slug.as_str(), "ok");
+        } else {
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(eyre!(
+            "{failures} of {} pokemon did not match",
+            expectations.len()
+        ));
+    }
+    println!("{} pokemon matched", expectations.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn default_expectations_parse() {
+        let expectations: Vec<Expectation> = serde_json::from_str(DEFAULT_EXPECTATIONS).unwrap();
+        let slugs: Vec<_> = expectations.iter().map(|e| e.slug.as_str()).collect();
+        assert_eq!(slugs, ["ho-oh", "squirtle", "bulbasaur"]);
+    }
+
+    #[test]
+    fn reports_only_mismatched_fields() {
+        let expected = json!({ "hp": 44, "name": "Squirtle", "legendary_or_mythical": false });
+        let body = json!({ "id": "0ujsswThIGTUYm2K8FjOOfXtY1K", "name": "Squirtle", "hp": 45 });
+
+        let Value::Object(expected) = expected else {
+            unreachable!()
+        };
+        assert_eq!(
+            mismatches(&expected, &body),
+            [
+                ("hp", &json!(44), &json!(45)),
+                ("legendary_or_mythical", &json!(false), &Value::Null)
+            ]
+        );
+    }
+}
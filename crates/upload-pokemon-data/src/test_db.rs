@@ -0,0 +1,17 @@
+//! `db_test!`, shared by the library's and the binary's tests.
+
+/// A `#[sqlx::test]` against a throwaway database with the tables from
+/// `create-tables.sql`. It's reported as ignored unless the `db-tests`
+/// feature is on, since it needs a MySQL server at `DATABASE_URL`.
+macro_rules! db_test {
+    (async fn $name:ident($pool:ident: $pool_type:ty) $body:block) => {
+        // every caller is in `src/`, so the path is the same for all of them
+        #[sqlx::test(migrations = false, fixtures("../create-tables.sql"))]
+        #[cfg_attr(
+            not(feature = "db-tests"),
+            ignore = "set DATABASE_URL and enable db-tests"
+        )]
+        async fn $name($pool: $pool_type) $body
+    };
+}
+pub(crate) use db_test;
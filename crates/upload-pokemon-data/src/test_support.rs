@@ -0,0 +1,29 @@
+//! Helpers shared by the unit tests.
+
+use crate::pokemon_csv::PokemonCsv;
+
+/// One dataset row, as a JSON dataset would have it.
+pub const PICHU_JSON: &str = r#"{
+    "name": "Pichu", "pokedex_id": 172, "abilities": "Static, Lightning Rod",
+    "typing": "Electric", "hp": 20, "attack": 40, "defense": 15,
+    "special_attack": 35, "special_defense": 35, "speed": 60, "height": 3,
+    "weight": 20, "generation": 2, "female_rate": 0.5, "genderless": "False",
+    "legendary/mythical": "False", "is_default": "True",
+    "forms_switchable": "False", "base_experience": 41, "capture_rate": 190,
+    "egg_groups": "Undiscovered", "base_happiness": 50, "evolves_from": null,
+    "primary_color": "yellow", "number_pokemon_with_typing": 62.0,
+    "normal_attack_effectiveness": 1.0, "fire_attack_effectiveness": 1.0,
+    "water_attack_effectiveness": 1.0, "electric_attack_effectiveness": 0.5,
+    "grass_attack_effectiveness": 1.0, "ice_attack_effectiveness": 1.0,
+    "fighting_attack_effectiveness": 1.0, "poison_attack_effectiveness": 1.0,
+    "ground_attack_effectiveness": 2.0, "fly_attack_effectiveness": 0.5,
+    "psychic_attack_effectiveness": 1.0, "bug_attack_effectiveness": 1.0,
+    "rock_attack_effectiveness": 1.0, "ghost_attack_effectiveness": 1.0,
+    "dragon_attack_effectiveness": 1.0, "dark_attack_effectiveness": 1.0,
+    "steel_attack_effectiveness": 0.5, "fairy_attack_effectiveness": 1.0
+}"#;
+
+/// `PICHU_JSON` deserialized, for tests that change a field or two.
+pub fn pichu() -> PokemonCsv {
+    serde_json::from_str(PICHU_JSON).unwrap()
+}
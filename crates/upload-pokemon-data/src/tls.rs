@@ -0,0 +1,72 @@
+use std::env;
+
+use sqlx::mysql::{MySqlConnectOptions, MySqlSslMode};
+
+/// `options` with TLS set from `DB_TLS_MODE` and `DB_TLS_CA_PATH`, shared by
+/// the uploader and the API so both can reach databases that insist on TLS,
+/// like RDS with its CA bundle.
+///
+/// Without `DB_TLS_MODE` the mode is left as it was, which is `preferred`
+/// unless a `DATABASE_URL` asked for something else with `?ssl-mode=`.
+pub fn tls_from_env(options: MySqlConnectOptions) -> Result<MySqlConnectOptions, String> {
+    tls_options(options, |name| env::var(name).ok())
+}
+
+fn tls_options(
+    mut options: MySqlConnectOptions,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<MySqlConnectOptions, String> {
+    if let Some(mode) = var("DB_TLS_MODE") {
+        options = options.ssl_mode(parse_tls_mode(&mode)?);
+    }
+    if let Some(ca_path) = var("DB_TLS_CA_PATH") {
+        options = options.ssl_ca(ca_path);
+    }
+    Ok(options)
+}
+
+fn parse_tls_mode(mode: &str) -> Result<MySqlSslMode, String> {
+    match mode {
+        "disabled" => Ok(MySqlSslMode::Disabled),
+        "preferred" => Ok(MySqlSslMode::Preferred),
+        "required" => Ok(MySqlSslMode::Required),
+        "verify-ca" => Ok(MySqlSslMode::VerifyCa),
+        "verify-identity" => Ok(MySqlSslMode::VerifyIdentity),
+        _ => Err(format!(
+            "DB_TLS_MODE must be one of disabled, preferred, required, verify-ca or \
+            verify-identity, got `{mode}`"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_mode_and_ca_path() {
+        assert!(matches!(
+            parse_tls_mode("verify-ca"),
+            Ok(MySqlSslMode::VerifyCa)
+        ));
+        assert!(parse_tls_mode("VerifyCa").is_err());
+
+        let vars = |name: &str| match name {
+            "DB_TLS_MODE" => Some("verify-identity".to_string()),
+            "DB_TLS_CA_PATH" => Some("/opt/rds-global-bundle.pem".to_string()),
+            _ => None,
+        };
+        let options = format!(
+            "{:?}",
+            tls_options(MySqlConnectOptions::new(), vars).unwrap()
+        );
+        assert!(options.contains("VerifyIdentity"));
+        assert!(options.contains("rds-global-bundle.pem"));
+
+        let unset = format!(
+            "{:?}",
+            tls_options(MySqlConnectOptions::new(), |_| None).unwrap()
+        );
+        assert!(unset.contains("Preferred"));
+    }
+}
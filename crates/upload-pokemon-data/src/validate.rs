@@ -0,0 +1,242 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::pokemon_csv::PokemonCsv;
+
+/// One problem with one field of one row of the dataset, serializable so it
+/// can be reported to tooling as well as to people.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    /// 1-based, counting data rows only, so the header isn't row 1.
+    pub row: usize,
+    pub field: String,
+    pub value: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(row: usize, field: &str, value: impl ToString, message: &str) -> Self {
+        Self {
+            row,
+            field: field.to_string(),
+            value: value.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "row {}, {} `{}`: {}",
+            self.row, self.field, self.value, self.message
+        )
+    }
+}
+
+/// Multipliers a single attack type can have against a pokemon.
+const EFFECTIVENESS: [f32; 6] = [0.0, 0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// Everything wrong with the `row`th pokemon of the dataset, checked before
+/// anything is written so one bad row doesn't leave a half loaded table.
+pub fn validate_row(row: usize, pokemon: &PokemonCsv) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let name_len = pokemon.name.chars().count();
+    if name_len == 0 || name_len > 30 {
+        errors.push(ValidationError::new(
+            row,
+            "name",
+            &pokemon.name,
+            "must be 1 to 30 characters",
+        ));
+    }
+    if !(1..=2).contains(&pokemon.typing.len()) {
+        let typing: Vec<_> = pokemon.typing.iter().map(|typing| typing.name()).collect();
+        errors.push(ValidationError::new(
+            row,
+            "typing",
+            typing.join(", "),
+            "must be one or two types",
+        ));
+    }
+    for (field, value) in [
+        ("hp", pokemon.hp),
+        ("attack", pokemon.attack),
+        ("defense", pokemon.defense),
+        ("special_attack", pokemon.special_attack),
+        ("special_defense", pokemon.special_defense),
+        ("speed", pokemon.speed),
+    ] {
+        if value == 0 {
+            errors.push(ValidationError::new(
+                row,
+                field,
+                value,
+                "must be at least 1",
+            ));
+        }
+    }
+    if !(1..=9).contains(&pokemon.generation) {
+        errors.push(ValidationError::new(
+            row,
+            "generation",
+            pokemon.generation,
+            "must be from 1 to 9",
+        ));
+    }
+    match (pokemon.genderless, pokemon.female_rate) {
+        (true, Some(female_rate)) => errors.push(ValidationError::new(
+            row,
+            "female_rate",
+            female_rate,
+            "must be empty for a genderless pokemon",
+        )),
+        (false, None) => errors.push(ValidationError::new(
+            row,
+            "female_rate",
+            "",
+            "is required unless the pokemon is genderless",
+        )),
+        (false, Some(female_rate)) if !(0.0..=1.0).contains(&female_rate) => errors.push(
+            ValidationError::new(row, "female_rate", female_rate, "must be from 0 to 1"),
+        ),
+        _ => {}
+    }
+    if pokemon.primary_color.chars().count() > 6 {
+        errors.push(ValidationError::new(
+            row,
+            "primary_color",
+            &pokemon.primary_color,
+            "must be at most 6 characters",
+        ));
+    }
+    for (field, value) in [
+        (
+            "normal_attack_effectiveness",
+            pokemon.normal_attack_effectiveness,
+        ),
+        (
+            "fire_attack_effectiveness",
+            pokemon.fire_attack_effectiveness,
+        ),
+        (
+            "water_attack_effectiveness",
+            pokemon.water_attack_effectiveness,
+        ),
+        (
+            "electric_attack_effectiveness",
+            pokemon.electric_attack_effectiveness,
+        ),
+        (
+            "grass_attack_effectiveness",
+            pokemon.grass_attack_effectiveness,
+        ),
+        ("ice_attack_effectiveness", pokemon.ice_attack_effectiveness),
+        (
+            "fighting_attack_effectiveness",
+            pokemon.fighting_attack_effectiveness,
+        ),
+        (
+            "poison_attack_effectiveness",
+            pokemon.poison_attack_effectiveness,
+        ),
+        (
+            "ground_attack_effectiveness",
+            pokemon.ground_attack_effectiveness,
+        ),
+        ("fly_attack_effectiveness", pokemon.fly_attack_effectiveness),
+        (
+            "psychic_attack_effectiveness",
+            pokemon.psychic_attack_effectiveness,
+        ),
+        ("bug_attack_effectiveness", pokemon.bug_attack_effectiveness),
+        (
+            "rock_attack_effectiveness",
+            pokemon.rock_attack_effectiveness,
+        ),
+        (
+            "ghost_attack_effectiveness",
+            pokemon.ghost_attack_effectiveness,
+        ),
+        (
+            "dragon_attack_effectiveness",
+            pokemon.dragon_attack_effectiveness,
+        ),
+        (
+            "dark_attack_effectiveness",
+            pokemon.dark_attack_effectiveness,
+        ),
+        (
+            "steel_attack_effectiveness",
+            pokemon.steel_attack_effectiveness,
+        ),
+        (
+            "fairy_attack_effectiveness",
+            pokemon.fairy_attack_effectiveness,
+        ),
+    ] {
+        if !EFFECTIVENESS.contains(&value) {
+            errors.push(ValidationError::new(
+                row,
+                field,
+                value,
+                "must be one of 0, 0.25, 0.5, 1, 2 or 4",
+            ));
+        }
+    }
+    errors
+}
+
+/// `validate_row` for every row, in order.
+pub fn validate_dataset(pokemon: &[PokemonCsv]) -> Vec<ValidationError> {
+    pokemon
+        .iter()
+        .enumerate()
+        .flat_map(|(index, pokemon)| validate_row(index + 1, pokemon))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::pichu;
+
+    #[test]
+    fn reports_each_bad_field_with_its_row() {
+        let mut broken = pichu();
+        broken.hp = 0;
+        broken.genderless = true;
+        broken.ground_attack_effectiveness = 3.0;
+
+        let errors = validate_dataset(&[pichu(), broken]);
+        assert_eq!(
+            errors,
+            [
+                ValidationError::new(2, "hp", 0, "must be at least 1"),
+                ValidationError::new(
+                    2,
+                    "female_rate",
+                    0.5,
+                    "must be empty for a genderless pokemon"
+                ),
+                ValidationError::new(
+                    2,
+                    "ground_attack_effectiveness",
+                    3,
+                    "must be one of 0, 0.25, 0.5, 1, 2 or 4"
+                ),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&errors[0]).unwrap(),
+            serde_json::json!({
+                "row": 2,
+                "field": "hp",
+                "value": "0",
+                "message": "must be at least 1"
+            })
+        );
+    }
+}